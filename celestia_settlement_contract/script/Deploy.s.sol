@@ -7,10 +7,10 @@ import "../src/L1CelestiaVerification.sol";
 contract DeployScript is Script {
     function run() external {
         // Read private key from environment variable
-        uint256 deployerPrivateKey = 0x8167e51f2c57e08b6eabb2ab84a39169527289292d26f62310ee0572d519f97e;
+        uint256 deployerPrivateKey = vm.envUint("PRIVATE_KEY");
         
-        // Get the Blobstream Oracle address from environment
-        address blobstreamAddress = 0xF0c6429ebAB2e7DC6e05DaFB61128bE21f13cb1e;
+        // Get the Blobstream Oracle address from environment (defaults to Sepolia)
+        address blobstreamAddress = vm.envOr("BLOBSTREAM_ADDRESS", address(0xF0c6429ebAB2e7DC6e05DaFB61128bE21f13cb1e));
         
         vm.startBroadcast(deployerPrivateKey);
 
@@ -0,0 +1,27 @@
+//! Submits a test blob and prints the verification data built for it.
+//!
+//! `cargo run --example celestia_prover`
+use celestia_integration::{CelestiaProver, Config};
+use std::error::Error;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let config = Config::load(None)?;
+    println!("Submitting blob and getting height.....");
+
+    let prover = CelestiaProver::from_config(&config.celestia).await?;
+
+    // Submit a blob and get its height and commitment
+    let (height, commitment) = prover.test_blob_submit().await?;
+
+    // Get all verification data
+    let verification_data = prover.prepare_verification_data(height, &commitment).await?;
+
+    println!("Verification data prepared successfully!");
+    println!("Start index: {}", verification_data.start_index);
+    println!("Data length: {}", verification_data.data_len);
+    println!("Data root: {:?}", verification_data.data_root_tuple.data_root);
+    println!("Shares proof: {:?}", verification_data.shares_proof);
+
+    Ok(())
+}
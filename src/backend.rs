@@ -0,0 +1,479 @@
+// src/backend.rs
+//! Data availability backends.
+//!
+//! The poster, the follower and the prover only need a handful of node
+//! queries, collected in [`DaBackend`]. [`CelestiaBackend`] forwards them to a
+//! celestia-node over JSON-RPC. [`MemoryBackend`] keeps a chain of real data
+//! squares in memory, so tests get genuine shares, row roots and NMT proofs
+//! without a node.
+
+use async_trait::async_trait;
+use celestia_proto::celestia::core::v1::proof::ShareProof as RawShareProof;
+use celestia_rpc::{BlobClient, Client, HeaderClient, ShareClient};
+use celestia_types::{
+    consts::appconsts::{AppVersion, SHARE_SIZE},
+    nmt::{Namespace, NamespaceProof, NS_SIZE},
+    row_namespace_data::NamespaceData,
+    sample::Sample,
+    AxisType, Blob, Commitment, DataAvailabilityHeader, ExtendedDataSquare, ShareProof, TxConfig,
+};
+use futures::stream::{BoxStream, StreamExt};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::error::{Error, Result};
+
+/// Node queries the crate relies on. Heights start at 1.
+#[async_trait]
+pub trait DaBackend: Send + Sync {
+    /// Submits `blobs` in one PayForBlobs transaction and returns its inclusion height.
+    async fn submit(&self, blobs: &[Blob], config: TxConfig) -> Result<u64>;
+
+    /// Height of the latest header the backend has.
+    async fn head(&self) -> Result<u64>;
+
+    /// Data availability header of the block at `height`.
+    async fn header(&self, height: u64) -> Result<DataAvailabilityHeader>;
+
+    /// Blobs of `namespaces` at `height` in square order, with their EDS index set.
+    async fn blobs(&self, height: u64, namespaces: &[Namespace]) -> Result<Vec<Blob>>;
+
+    /// The blob with `commitment` at `height`.
+    async fn blob(&self, height: u64, namespace: Namespace, commitment: Commitment) -> Result<Blob>;
+
+    /// Shares of `namespace` at `height` with their NMT proofs, one entry per row.
+    async fn namespace_data(&self, height: u64, namespace: Namespace) -> Result<NamespaceData>;
+
+    /// Share at `row`, `column` of the original data square at `height`, with
+    /// its NMT proof against the row root.
+    async fn sample(&self, height: u64, row: u16, column: u16) -> Result<Sample>;
+
+    /// Proof of the shares `start..end` of the original data square at
+    /// `height` against its data root: an NMT proof per row the range
+    /// touches, plus those rows' roots and their proofs to the data root.
+    async fn share_proof(&self, height: u64, start: u64, end: u64) -> Result<ShareProof>;
+
+    /// Pushes the height of every new header, if the backend can.
+    ///
+    /// Callers poll [`DaBackend::head`] when this returns `None`.
+    async fn subscribe_heads(&self) -> Result<Option<BoxStream<'static, u64>>> {
+        Ok(None)
+    }
+}
+
+/// Lets a backend be shared, e.g. between a client and the test driving it.
+#[async_trait]
+impl<T: DaBackend + ?Sized> DaBackend for Arc<T> {
+    async fn submit(&self, blobs: &[Blob], config: TxConfig) -> Result<u64> {
+        (**self).submit(blobs, config).await
+    }
+
+    async fn head(&self) -> Result<u64> {
+        (**self).head().await
+    }
+
+    async fn header(&self, height: u64) -> Result<DataAvailabilityHeader> {
+        (**self).header(height).await
+    }
+
+    async fn blobs(&self, height: u64, namespaces: &[Namespace]) -> Result<Vec<Blob>> {
+        (**self).blobs(height, namespaces).await
+    }
+
+    async fn blob(&self, height: u64, namespace: Namespace, commitment: Commitment) -> Result<Blob> {
+        (**self).blob(height, namespace, commitment).await
+    }
+
+    async fn namespace_data(&self, height: u64, namespace: Namespace) -> Result<NamespaceData> {
+        (**self).namespace_data(height, namespace).await
+    }
+
+    async fn sample(&self, height: u64, row: u16, column: u16) -> Result<Sample> {
+        (**self).sample(height, row, column).await
+    }
+
+    async fn share_proof(&self, height: u64, start: u64, end: u64) -> Result<ShareProof> {
+        (**self).share_proof(height, start, end).await
+    }
+
+    async fn subscribe_heads(&self) -> Result<Option<BoxStream<'static, u64>>> {
+        (**self).subscribe_heads().await
+    }
+}
+
+/// Backend talking to a celestia-node.
+pub struct CelestiaBackend {
+    client: Client,
+}
+
+impl CelestiaBackend {
+    pub async fn connect(node_url: &str, auth_token: Option<&str>) -> Result<Self> {
+        let client = Client::new(node_url, auth_token).await?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl DaBackend for CelestiaBackend {
+    async fn submit(&self, blobs: &[Blob], config: TxConfig) -> Result<u64> {
+        Ok(self.client.blob_submit(blobs, config).await?)
+    }
+
+    async fn head(&self) -> Result<u64> {
+        Ok(self.client.header_local_head().await?.height().value())
+    }
+
+    async fn header(&self, height: u64) -> Result<DataAvailabilityHeader> {
+        Ok(self.client.header_get_by_height(height).await?.dah)
+    }
+
+    async fn blobs(&self, height: u64, namespaces: &[Namespace]) -> Result<Vec<Blob>> {
+        Ok(self.client.blob_get_all(height, namespaces).await?.unwrap_or_default())
+    }
+
+    async fn blob(&self, height: u64, namespace: Namespace, commitment: Commitment) -> Result<Blob> {
+        Ok(self.client.blob_get(height, namespace, commitment).await?)
+    }
+
+    async fn namespace_data(&self, height: u64, namespace: Namespace) -> Result<NamespaceData> {
+        let header = self.client.header_get_by_height(height).await?;
+        Ok(self.client.share_get_namespace_data(&header, namespace).await?)
+    }
+
+    async fn sample(&self, height: u64, row: u16, column: u16) -> Result<Sample> {
+        let header = self.client.header_get_by_height(height).await?;
+        let ods_width = u64::from(header.dah.square_width() / 2);
+        let index = u64::from(row) * ods_width + u64::from(column);
+        // the single-share range proof is the share's NMT proof in its row,
+        // plus the row root's proof to the data root that we do not need here
+        let range = self.client.share_get_range(&header, index, index + 1).await?;
+        let malformed = || Error::Proof(format!("node returned no proof for share {row}:{column} at height {height}"));
+        let share = range.shares.into_iter().next().ok_or_else(malformed)?;
+        let proof = RawShareProof::from(range.proof).share_proofs.into_iter().next().ok_or_else(malformed)?;
+        Ok(Sample { proof_type: AxisType::Row, share, proof: NamespaceProof::try_from(proof)? })
+    }
+
+    async fn share_proof(&self, height: u64, start: u64, end: u64) -> Result<ShareProof> {
+        let header = self.client.header_get_by_height(height).await?;
+        Ok(self.client.share_get_range(&header, start, end).await?.proof)
+    }
+
+    async fn subscribe_heads(&self) -> Result<Option<BoxStream<'static, u64>>> {
+        // plain HTTP connections cannot carry subscriptions
+        let headers = match self.client.header_subscribe().await {
+            Ok(headers) => headers,
+            Err(e) => {
+                tracing::info!("Header subscription unavailable: {}", e);
+                return Ok(None);
+            }
+        };
+        let heights = headers.filter_map(|header| async move {
+            match header {
+                Ok(header) => Some(header.height().value()),
+                Err(e) => {
+                    tracing::warn!("Skipping malformed header notification: {}", e);
+                    None
+                }
+            }
+        });
+        Ok(Some(heights.boxed()))
+    }
+}
+
+/// Deterministic chain kept in memory.
+///
+/// Every submission produces one block holding just its blobs, ordered by
+/// namespace and packed into the smallest square that fits, followed by tail
+/// padding. Squares are extended and committed to like on Celestia, so spans,
+/// commitments and NMT proofs taken from them are real; only the layout rules
+/// of celestia-app (PFB transactions, blob alignment) are left out.
+pub struct MemoryBackend {
+    state: Mutex<MemoryState>,
+    heads: broadcast::Sender<u64>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    blocks: Vec<MemoryBlock>,
+    failures: VecDeque<String>,
+    gas_prices: Vec<Option<f64>>,
+    /// Heights whose shares cannot be sampled.
+    withheld: Vec<u64>,
+    /// Heights whose blobs are served with altered data.
+    altered: Vec<u64>,
+}
+
+struct MemoryBlock {
+    eds: ExtendedDataSquare,
+    dah: DataAvailabilityHeader,
+    blobs: Vec<Blob>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(MemoryState::default()),
+            heads: broadcast::channel(64).0,
+        }
+    }
+
+    /// Appends `count` blocks without blobs, e.g. to confirm earlier ones.
+    pub fn produce_empty_blocks(&self, count: u64) -> Result<u64> {
+        let mut height = 0;
+        for _ in 0..count {
+            height = self.produce_block(Vec::new())?;
+        }
+        Ok(height)
+    }
+
+    /// Makes the next submission fail with `message`, as if the node rejected it.
+    pub fn fail_next_submit(&self, message: impl Into<String>) {
+        self.state().failures.push_back(message.into());
+    }
+
+    /// Makes sampling the block at `height` fail, as if its data was withheld.
+    pub fn withhold_shares(&self, height: u64) {
+        self.state().withheld.push(height);
+    }
+
+    /// Makes blobs fetched by commitment at `height` come back with altered
+    /// data, as from a node serving something else than was submitted.
+    pub fn alter_blobs(&self, height: u64) {
+        self.state().altered.push(height);
+    }
+
+    /// Gas price of every submission attempt so far, failed ones included.
+    pub fn gas_prices(&self) -> Vec<Option<f64>> {
+        self.state().gas_prices.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn produce_block(&self, mut blobs: Vec<Blob>) -> Result<u64> {
+        blobs.sort_by_key(|blob| blob.namespace);
+
+        let mut ods = Vec::new();
+        for blob in &mut blobs {
+            blob.index = Some(ods.len() as u64);
+            ods.extend(blob.to_shares()?.into_iter().map(|share| share.as_ref().to_vec()));
+        }
+        let mut ods_width = 1;
+        while ods_width * ods_width < ods.len() {
+            ods_width *= 2;
+        }
+        let tail_padding = [Namespace::TAIL_PADDING.as_bytes(), &[0; SHARE_SIZE - NS_SIZE][..]].concat();
+        ods.resize(ods_width * ods_width, tail_padding);
+
+        // blob positions are reported in the extended square
+        for blob in &mut blobs {
+            let index = blob.index.expect("set above") as usize;
+            blob.index = Some(((index / ods_width) * 2 * ods_width + index % ods_width) as u64);
+        }
+
+        let eds = ExtendedDataSquare::from_ods(ods, AppVersion::V2)?;
+        let dah = DataAvailabilityHeader::from_eds(&eds);
+        let height = {
+            let mut state = self.state();
+            state.blocks.push(MemoryBlock { eds, dah, blobs });
+            state.blocks.len() as u64
+        };
+        // nobody listening is fine
+        let _ = self.heads.send(height);
+        Ok(height)
+    }
+
+    fn with_block<T>(&self, height: u64, f: impl FnOnce(&MemoryBlock) -> Result<T>) -> Result<T> {
+        let state = self.state();
+        let head = state.blocks.len() as u64;
+        match height.checked_sub(1).and_then(|i| state.blocks.get(i as usize)) {
+            Some(block) => f(block),
+            None => Err(Error::HeightUnavailable { height, head }),
+        }
+    }
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl DaBackend for MemoryBackend {
+    async fn submit(&self, blobs: &[Blob], config: TxConfig) -> Result<u64> {
+        let failure = {
+            let mut state = self.state();
+            state.gas_prices.push(config.gas_price);
+            state.failures.pop_front()
+        };
+        if let Some(message) = failure {
+            return Err(jsonrpsee::core::ClientError::Custom(message).into());
+        }
+        self.produce_block(blobs.to_vec())
+    }
+
+    async fn head(&self) -> Result<u64> {
+        Ok(self.state().blocks.len() as u64)
+    }
+
+    async fn header(&self, height: u64) -> Result<DataAvailabilityHeader> {
+        self.with_block(height, |block| Ok(block.dah.clone()))
+    }
+
+    async fn blobs(&self, height: u64, namespaces: &[Namespace]) -> Result<Vec<Blob>> {
+        self.with_block(height, |block| {
+            Ok(block
+                .blobs
+                .iter()
+                .filter(|blob| namespaces.contains(&blob.namespace))
+                .cloned()
+                .collect())
+        })
+    }
+
+    async fn blob(&self, height: u64, namespace: Namespace, commitment: Commitment) -> Result<Blob> {
+        let altered = self.state().altered.contains(&height);
+        let mut blob = self.with_block(height, |block| {
+            block
+                .blobs
+                .iter()
+                .find(|blob| blob.namespace == namespace && blob.commitment == commitment)
+                .cloned()
+                .ok_or(Error::BlobNotFound { height, commitment })
+        })?;
+        if altered {
+            blob.data[0] ^= 1;
+        }
+        Ok(blob)
+    }
+
+    async fn namespace_data(&self, height: u64, namespace: Namespace) -> Result<NamespaceData> {
+        self.with_block(height, |block| {
+            let rows = block.eds.get_namespace_data(namespace, &block.dah, height)?;
+            Ok(NamespaceData { rows: rows.into_iter().map(|(_, row)| row).collect() })
+        })
+    }
+
+    async fn sample(&self, height: u64, row: u16, column: u16) -> Result<Sample> {
+        if self.state().withheld.contains(&height) {
+            let message = format!("share {row}:{column} at height {height} is not available");
+            return Err(jsonrpsee::core::ClientError::Custom(message).into());
+        }
+        self.with_block(height, |block| Ok(Sample::new(row, column, AxisType::Row, &block.eds)?))
+    }
+
+    async fn share_proof(&self, height: u64, start: u64, end: u64) -> Result<ShareProof> {
+        self.with_block(height, |block| {
+            let width = u64::from(block.dah.square_width() / 2);
+            if start >= end || end > width * width {
+                return Err(Error::Proof(format!("share range {start}..{end} is not in the square at height {height}")));
+            }
+            let (first_row, last_row) = ((start / width) as u16, ((end - 1) / width) as u16);
+
+            let mut data = Vec::new();
+            let mut share_proofs = Vec::new();
+            for row in first_row..=last_row {
+                let row_start = u64::from(row) * width;
+                let columns = start.max(row_start) - row_start..end.min(row_start + width) - row_start;
+                for column in columns.clone() {
+                    data.push(block.eds.share(row, column as u16)?.as_ref().to_vec());
+                }
+                let proof = block.eds.row_nmt(row)?.build_range_proof(columns.start as usize..columns.end as usize);
+                let proof = nmt_rs::nmt_proof::NamespaceProof::PresenceProof { proof, ignore_max_ns: true };
+                share_proofs.push(NamespaceProof::from(proof).into());
+            }
+
+            let namespace = block.eds.share(first_row, (start % width) as u16)?.namespace();
+            let proof = RawShareProof {
+                data,
+                namespace_id: namespace.id().to_vec(),
+                namespace_version: u32::from(namespace.version()),
+                share_proofs,
+                row_proof: Some(block.dah.row_proof(first_row..=last_row)?.into()),
+            };
+            Ok(ShareProof::try_from(proof)?)
+        })
+    }
+
+    async fn subscribe_heads(&self) -> Result<Option<BoxStream<'static, u64>>> {
+        let heads = futures::stream::unfold(self.heads.subscribe(), |mut heads| async move {
+            loop {
+                match heads.recv().await {
+                    Ok(height) => return Some((height, heads)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(Some(heads.boxed()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span;
+
+    fn blob(namespace: &[u8], len: usize) -> Blob {
+        Blob::new(Namespace::new_v0(namespace).unwrap(), vec![len as u8; len], AppVersion::V2).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_blocks() {
+        let backend = MemoryBackend::new();
+        assert_eq!(backend.head().await.unwrap(), 0);
+
+        let ours = blob(b"rollup", 1000);
+        let other = blob(b"filler", 10);
+        let height = backend.submit(&[ours.clone(), other.clone()], TxConfig::default()).await.unwrap();
+        assert_eq!(height, 1);
+
+        // filler sorts first and takes share 0; ours takes 1..4 of a 2 wide ODS
+        let dah = backend.header(height).await.unwrap();
+        assert_eq!(dah.square_width(), 4);
+        let fetched = backend.blob(height, ours.namespace, ours.commitment).await.unwrap();
+        assert_eq!(fetched.data, ours.data);
+        let span = span::blob_span(height, &fetched, dah.square_width()).unwrap();
+        assert_eq!((span.start_index, span.data_len), (1, 3));
+
+        let data = backend.namespace_data(height, ours.namespace).await.unwrap();
+        span::verify_namespace_data(height, ours.namespace, &data, &dah).unwrap();
+        assert_eq!(span::find_blob_span(height, &data, &dah, &ours.commitment).unwrap(), span);
+        // the range crosses into the second row, so it needs two NMT proofs
+        let proof = backend.share_proof(height, span.start_index, span.end_index()).await.unwrap();
+        proof.verify(dah.hash()).unwrap();
+        assert_eq!(proof.shares().len(), 3);
+        assert!(backend.share_proof(height, 3, 5).await.is_err());
+
+        assert_eq!(backend.blobs(height, &[other.namespace]).await.unwrap().len(), 1);
+        assert!(matches!(
+            backend.blob(height, ours.namespace, other.commitment).await,
+            Err(Error::BlobNotFound { height: 1, .. })
+        ));
+
+        assert_eq!(backend.produce_empty_blocks(2).unwrap(), 3);
+        assert!(backend.blobs(3, &[ours.namespace]).await.unwrap().is_empty());
+        assert!(matches!(
+            backend.header(4).await,
+            Err(Error::HeightUnavailable { height: 4, head: 3 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_is_deterministic() {
+        let blobs = [blob(b"rollup", 700), blob(b"filler", 20), blob(b"rollup", 5)];
+        let (a, b) = (MemoryBackend::new(), MemoryBackend::new());
+        a.submit(&blobs, TxConfig::default()).await.unwrap();
+        b.submit(&blobs, TxConfig::default()).await.unwrap();
+        assert_eq!(a.header(1).await.unwrap().hash(), b.header(1).await.unwrap().hash());
+
+        a.fail_next_submit("insufficient fee");
+        let config = TxConfig { gas_price: Some(0.004), ..TxConfig::default() };
+        assert!(a.submit(&blobs, config).await.is_err());
+        assert_eq!(a.head().await.unwrap(), 1);
+        assert_eq!(a.gas_prices(), vec![None, Some(0.004)]);
+    }
+}
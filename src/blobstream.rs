@@ -0,0 +1,455 @@
+// src/blobstream.rs
+//! Blobstream data commitments, indexed from L1 events.
+//!
+//! Blobstream attests Celestia data roots in batches: every
+//! `DataCommitmentStored` event stores the root of the data root tuples of a
+//! range of Celestia heights under the next proof nonce. Settling a span
+//! means naming that nonce and proving the span's tuple against the range, so
+//! [`BlobstreamIndexer`] scans the events with alloy, keeps every range it
+//! saw in a [`CommitmentCache`] and answers which commitment covers a height.
+//! With a cache path configured the cache is stored after every scanned
+//! window, and later runs only scan the L1 blocks added since.
+//!
+//! Attestations lag Celestia by up to a few hours, so settlement first waits
+//! in [`BlobstreamIndexer::wait_for`]: it polls the contract's `latestBlock()`
+//! and only scans events once that has moved past the height.
+
+use alloy::{
+    primitives::Address,
+    rpc::types::{Filter, Log},
+    sol,
+    sol_types::SolEvent,
+};
+use alloy_provider::{Provider, ProviderBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(feature = "celestia")]
+use crate::celestia_prover::CelestiaProver;
+use crate::config::{BlobstreamConfig, ConfigError, L1Config};
+use crate::error::{Error, Result};
+use crate::proof::{AttestationProof, DataCommitment};
+use crate::settlement_verification::L1SettlementContract;
+use Blobstream::DataCommitmentStored;
+
+sol! {
+    #[sol(rpc)]
+    contract Blobstream {
+        event DataCommitmentStored(
+            uint256 proofNonce,
+            uint64 indexed startBlock,
+            uint64 indexed endBlock,
+            bytes32 indexed dataCommitment
+        );
+
+        function latestBlock() external view returns (uint64);
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlobstreamError {
+    #[error("blobstream cache {path}: {source}")]
+    CacheIo {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("blobstream cache {path} is corrupt: {source}")]
+    CacheCorrupt {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error("malformed DataCommitmentStored event in L1 block {block:?}: {reason}")]
+    MalformedEvent { block: Option<u64>, reason: String },
+
+    /// A contract call failed for another reason than the transport.
+    #[error("blobstream call failed: {0}")]
+    Call(alloy::contract::Error),
+
+    /// The tuples rebuilt from Celestia headers do not give the stored root.
+    #[error("data commitment {nonce} stores root {stored}, the headers of its range give {computed}")]
+    RootMismatch { nonce: u64, stored: String, computed: String },
+
+    /// Blobstream did not attest the height within the configured timeout.
+    #[error("Celestia height {height} not attested after the timeout, Blobstream is at {latest_block:?}")]
+    AttestationTimeout { height: u64, latest_block: Option<u64> },
+}
+
+/// A data commitment Blobstream stored, with its root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredCommitment {
+    #[serde(flatten)]
+    pub commitment: DataCommitment,
+    pub root: [u8; 32],
+}
+
+impl StoredCommitment {
+    /// Checks that `attestation` was built for this commitment.
+    pub fn check(&self, attestation: &AttestationProof) -> Result<(), BlobstreamError> {
+        if attestation.nonce != self.commitment.nonce || attestation.data_commitment != self.root {
+            return Err(BlobstreamError::RootMismatch {
+                nonce: self.commitment.nonce,
+                stored: hex::encode(self.root),
+                computed: hex::encode(attestation.data_commitment),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Commitments seen so far and how far L1 was scanned for them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentCache {
+    /// First L1 block not scanned yet.
+    pub next_block: u64,
+    /// By first Celestia height.
+    pub commitments: BTreeMap<u64, StoredCommitment>,
+}
+
+impl CommitmentCache {
+    pub fn starting_at(block: u64) -> Self {
+        Self { next_block: block, commitments: BTreeMap::new() }
+    }
+
+    /// Reads the cache at `path`, `None` if the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, BlobstreamError> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => return Err(BlobstreamError::CacheIo { path: path.to_path_buf(), source }),
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|source| BlobstreamError::CacheCorrupt { path: path.to_path_buf(), source })
+    }
+
+    /// Replaces the cache at `path` atomically.
+    pub fn store(&self, path: &Path) -> Result<(), BlobstreamError> {
+        let io = |source| BlobstreamError::CacheIo { path: path.to_path_buf(), source };
+        let tmp = path.with_extension("tmp");
+        let data = serde_json::to_vec(self).expect("cache serializes to JSON");
+        std::fs::write(&tmp, data).map_err(io)?;
+        std::fs::rename(&tmp, path).map_err(io)
+    }
+
+    pub fn insert(&mut self, stored: StoredCommitment) {
+        self.commitments.insert(stored.commitment.start, stored);
+    }
+
+    /// The commitment whose range holds Celestia `height`.
+    pub fn covering(&self, height: u64) -> Option<StoredCommitment> {
+        let (_, stored) = self.commitments.range(..=height).next_back()?;
+        stored.commitment.contains(height).then_some(*stored)
+    }
+
+    /// Highest Celestia height any known commitment covers.
+    pub fn last_height(&self) -> Option<u64> {
+        self.commitments.values().map(|stored| stored.commitment.end - 1).max()
+    }
+}
+
+/// Follows Blobstream's `latestBlock()` towards a Celestia height, reporting
+/// each new value once.
+#[derive(Debug, Clone)]
+pub struct AttestationTracker {
+    height: u64,
+    latest_block: Option<u64>,
+}
+
+impl AttestationTracker {
+    pub fn new(height: u64) -> Self {
+        Self { height, latest_block: None }
+    }
+
+    /// Records `latestBlock()` and returns it if it moved forward.
+    pub fn observe(&mut self, latest_block: u64) -> Option<u64> {
+        match self.latest_block {
+            Some(seen) if seen >= latest_block => None,
+            _ => {
+                self.latest_block = Some(latest_block);
+                Some(latest_block)
+            }
+        }
+    }
+
+    pub fn latest_block(&self) -> Option<u64> {
+        self.latest_block
+    }
+
+    /// Whether a stored commitment covers the height; commitment ranges end
+    /// before the block they name, so `latestBlock()` must be past it.
+    pub fn is_attested(&self) -> bool {
+        self.latest_block.is_some_and(|latest| latest > self.height)
+    }
+}
+
+/// Decodes a `DataCommitmentStored` log.
+pub fn decode_commitment(log: &Log) -> Result<StoredCommitment, BlobstreamError> {
+    let malformed = |reason: String| BlobstreamError::MalformedEvent { block: log.block_number, reason };
+    let event = log.log_decode::<DataCommitmentStored>().map_err(|e| malformed(e.to_string()))?.inner.data;
+    let nonce = u64::try_from(event.proofNonce).map_err(|_| malformed(format!("nonce {} overflows", event.proofNonce)))?;
+    if event.endBlock <= event.startBlock {
+        return Err(malformed(format!("empty range {}..{}", event.startBlock, event.endBlock)));
+    }
+    Ok(StoredCommitment {
+        commitment: DataCommitment { nonce, start: event.startBlock, end: event.endBlock },
+        root: event.dataCommitment.0,
+    })
+}
+
+/// Maps contract call failures, keeping transport errors retryable.
+pub(crate) fn call_error(e: alloy::contract::Error) -> Error {
+    match e {
+        alloy::contract::Error::TransportError(e) => e.into(),
+        e => BlobstreamError::Call(e).into(),
+    }
+}
+
+/// Follows the commitments of one Blobstream contract.
+pub struct BlobstreamIndexer {
+    rpc_url: reqwest::Url,
+    address: Address,
+    config: BlobstreamConfig,
+    cache: CommitmentCache,
+}
+
+impl BlobstreamIndexer {
+    /// Loads the cache, if there is one, and resolves the Blobstream address.
+    ///
+    /// Without a cache to resume, `start_block` must be set: scanning from
+    /// the L1 genesis would take thousands of `eth_getLogs` calls.
+    pub async fn from_config(l1: &L1Config) -> Result<Self> {
+        let cached = match &l1.blobstream.cache_path {
+            Some(path) => CommitmentCache::load(path)?,
+            None => None,
+        };
+        let cache = match (cached, l1.blobstream.start_block) {
+            (Some(cache), _) => cache,
+            (None, Some(block)) => CommitmentCache::starting_at(block),
+            (None, None) => {
+                return Err(ConfigError::Invalid {
+                    field: "l1.blobstream.start_block",
+                    reason: "required to scan for Blobstream commitments, e.g. the contract's deployment block"
+                        .to_string(),
+                }
+                .into())
+            }
+        };
+        let rpc_url = l1.rpc_url()?;
+        let address = match l1.blobstream.address {
+            Some(address) => address,
+            None => {
+                let provider = ProviderBuilder::new().on_http(rpc_url.clone());
+                let settlement = L1SettlementContract::new(l1.contract_address, provider);
+                settlement.blobstream().call().await.map_err(call_error)?._0
+            }
+        };
+        Ok(Self { rpc_url, address, config: l1.blobstream.clone(), cache })
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn cache(&self) -> &CommitmentCache {
+        &self.cache
+    }
+
+    /// Scans the events up to the L1 head.
+    pub async fn sync(&mut self) -> Result<()> {
+        let provider = ProviderBuilder::new().on_http(self.rpc_url.clone());
+        let head = provider.get_block_number().await?;
+
+        while self.cache.next_block <= head {
+            let from = self.cache.next_block;
+            let to = head.min(from.saturating_add(self.config.max_block_range - 1));
+            let filter = Filter::new()
+                .address(self.address)
+                .event_signature(DataCommitmentStored::SIGNATURE_HASH)
+                .from_block(from)
+                .to_block(to);
+            let logs = provider.get_logs(&filter).await?;
+            for log in &logs {
+                self.cache.insert(decode_commitment(log)?);
+            }
+            self.cache.next_block = to + 1;
+            if let Some(path) = &self.config.cache_path {
+                self.cache.store(path)?;
+            }
+            tracing::debug!("Scanned L1 blocks {}..={} for Blobstream commitments: {} found", from, to, logs.len());
+        }
+        Ok(())
+    }
+
+    /// The commitment covering Celestia `height`, scanning new events only
+    /// when the cache has none.
+    pub async fn find(&mut self, height: u64) -> Result<Option<StoredCommitment>> {
+        if let Some(stored) = self.cache.covering(height) {
+            return Ok(Some(stored));
+        }
+        self.sync().await?;
+        Ok(self.cache.covering(height))
+    }
+
+    /// Waits until a stored commitment covers Celestia `height` and returns it.
+    ///
+    /// Fails with [`BlobstreamError::AttestationTimeout`] when Blobstream
+    /// does not get there within `attestation_timeout_secs`.
+    pub async fn wait_for(&mut self, height: u64) -> Result<StoredCommitment> {
+        if let Some(stored) = self.cache.covering(height) {
+            return Ok(stored);
+        }
+        let provider = ProviderBuilder::new().on_http(self.rpc_url.clone());
+        let blobstream = Blobstream::new(self.address, provider);
+        let mut tracker = AttestationTracker::new(height);
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(self.config.attestation_timeout_secs);
+        loop {
+            let latest = blobstream.latestBlock().call().await.map_err(call_error)?._0;
+            let moved = tracker.observe(latest);
+            if tracker.is_attested() {
+                // the event may not be visible through the RPC node yet, in
+                // which case the next poll scans again
+                if let Some(stored) = self.find(height).await? {
+                    tracing::info!("Celestia height {} attested by Blobstream nonce {}", height, stored.commitment.nonce);
+                    return Ok(stored);
+                }
+            } else if let Some(latest) = moved {
+                tracing::info!(
+                    "Waiting for Blobstream to attest Celestia height {}: attested up to {}",
+                    height,
+                    latest.saturating_sub(1)
+                );
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(BlobstreamError::AttestationTimeout { height, latest_block: tracker.latest_block() }.into());
+            }
+            tokio::time::sleep(Duration::from_millis(self.config.poll_interval_ms)).await;
+        }
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl CelestiaProver {
+    /// Proves the data root tuple of `height` against a stored commitment,
+    /// checking that Celestia's headers give the root Blobstream stored.
+    pub async fn get_stored_attestation_proof(
+        &self,
+        height: u64,
+        stored: &StoredCommitment,
+    ) -> Result<AttestationProof> {
+        let attestation = self.get_attestation_proof(height, &stored.commitment).await?;
+        stored.check(&attestation)?;
+        Ok(attestation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{B256, U256};
+
+    fn event_log(nonce: u64, start: u64, end: u64, root: [u8; 32]) -> Log {
+        let event = DataCommitmentStored {
+            proofNonce: U256::from(nonce),
+            startBlock: start,
+            endBlock: end,
+            dataCommitment: B256::from(root),
+        };
+        Log {
+            inner: alloy::primitives::Log { address: Address::repeat_byte(7), data: event.encode_log_data() },
+            block_number: Some(100 + nonce),
+            ..Log::default()
+        }
+    }
+
+    #[test]
+    fn test_attestation_tracker() {
+        let mut tracker = AttestationTracker::new(100);
+        assert_eq!(tracker.observe(90), Some(90));
+        assert_eq!(tracker.observe(90), None);
+        // a lagging RPC node does not move the tracker back
+        assert_eq!(tracker.observe(80), None);
+        assert_eq!(tracker.observe(100), Some(100));
+        assert!(!tracker.is_attested());
+        assert_eq!(tracker.observe(101), Some(101));
+        assert!(tracker.is_attested());
+        assert_eq!(tracker.latest_block(), Some(101));
+    }
+
+    #[tokio::test]
+    async fn test_start_block_is_required() {
+        let mut l1 = L1Config::default();
+        l1.blobstream.address = Some(Address::repeat_byte(7));
+        assert!(matches!(
+            BlobstreamIndexer::from_config(&l1).await,
+            Err(Error::Config(ConfigError::Invalid { field: "l1.blobstream.start_block", .. }))
+        ));
+
+        // a cached scan is resumed without it
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blobstream.json");
+        CommitmentCache::starting_at(1234).store(&path).unwrap();
+        l1.blobstream.cache_path = Some(path);
+        let indexer = BlobstreamIndexer::from_config(&l1).await.unwrap();
+        assert_eq!(indexer.cache().next_block, 1234);
+    }
+
+    #[test]
+    fn test_commitment_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blobstream.json");
+        assert_eq!(CommitmentCache::load(&path).unwrap(), None);
+
+        let mut cache = CommitmentCache::starting_at(50);
+        for (nonce, start, end) in [(1, 1, 11), (2, 11, 21), (4, 31, 41)] {
+            cache.insert(decode_commitment(&event_log(nonce, start, end, [nonce as u8; 32])).unwrap());
+        }
+        cache.next_block = 120;
+        cache.store(&path).unwrap();
+        let cache = CommitmentCache::load(&path).unwrap().unwrap();
+
+        assert_eq!(cache.next_block, 120);
+        assert_eq!(cache.covering(11).unwrap().commitment, DataCommitment { nonce: 2, start: 11, end: 21 });
+        assert_eq!(cache.covering(20).unwrap().root, [2; 32]);
+        assert_eq!(cache.covering(0), None);
+        // the commitment for 21..31 has not been seen
+        assert_eq!(cache.covering(25), None);
+        assert_eq!(cache.covering(41), None);
+        assert_eq!(cache.last_height(), Some(40));
+
+        let malformed = decode_commitment(&event_log(3, 21, 21, [3; 32])).unwrap_err();
+        assert!(matches!(malformed, BlobstreamError::MalformedEvent { block: Some(103), .. }));
+
+        std::fs::write(&path, b"{\"next_block\":").unwrap();
+        assert!(matches!(CommitmentCache::load(&path), Err(BlobstreamError::CacheCorrupt { .. })));
+    }
+
+    #[cfg(feature = "celestia")]
+    #[tokio::test]
+    async fn test_stored_attestation_proof() {
+        use crate::backend::MemoryBackend;
+        use celestia_types::nmt::Namespace;
+        use std::sync::Arc;
+
+        let backend = Arc::new(MemoryBackend::new());
+        let prover = CelestiaProver::with_backend(Box::new(backend.clone()), Namespace::new_v0(b"rollup").unwrap());
+        let (height, _) = prover.test_blob_submit().await.unwrap();
+        backend.produce_empty_blocks(3).unwrap();
+
+        let commitment = DataCommitment { nonce: 5, start: 1, end: 5 };
+        let root = prover.get_attestation_proof(height, &commitment).await.unwrap().data_commitment;
+        let stored = StoredCommitment { commitment, root };
+        let attestation = prover.get_stored_attestation_proof(height, &stored).await.unwrap();
+        assert_eq!(attestation.nonce, 5);
+
+        // a root the headers do not give is rejected
+        let forged = StoredCommitment { root: [0; 32], ..stored };
+        assert!(matches!(
+            prover.get_stored_attestation_proof(height, &forged).await,
+            Err(Error::Blobstream(BlobstreamError::RootMismatch { nonce: 5, .. }))
+        ));
+    }
+}
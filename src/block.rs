@@ -0,0 +1,248 @@
+// src/block.rs
+//! Binary encoding of rollup blocks posted as blob data.
+//!
+//! Layout of version 1, all integers big endian:
+//!
+//! ```text
+//! version       u8    (= 1)
+//! body_len      u32   length of everything below
+//! number        u64
+//! timestamp     u64
+//! parent_hash   [u8; 32]
+//! state_root    [u8; 32]
+//! tx_count      u32
+//! tx_count x { len u32, bytes [u8; len] }
+//! ```
+//!
+//! The leading version byte lets the format evolve; decoders reject versions
+//! they do not know instead of guessing.
+
+/// Current encoding version written by [`RollupBlock::encode`].
+pub const BLOCK_VERSION_V1: u8 = 1;
+
+const HEADER_LEN: usize = 8 + 8 + 32 + 32;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum CodecError {
+    #[error("unknown rollup block version {0}")]
+    UnknownVersion(u8),
+
+    #[error("rollup block truncated: needed {needed} more bytes")]
+    Truncated { needed: usize },
+
+    #[error("{0} trailing bytes after rollup block")]
+    TrailingBytes(usize),
+
+    #[error("rollup block field too long: {0} bytes")]
+    TooLong(usize),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub number: u64,
+    /// Unix time in seconds.
+    pub timestamp: u64,
+    pub parent_hash: [u8; 32],
+    pub state_root: [u8; 32],
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollupBlock {
+    pub header: BlockHeader,
+    pub transactions: Vec<Vec<u8>>,
+}
+
+impl RollupBlock {
+    /// Size of the version 1 encoding.
+    pub fn encoded_len(&self) -> usize {
+        1 + 4 + self.body_len()
+    }
+
+    fn body_len(&self) -> usize {
+        HEADER_LEN + 4 + self.transactions.iter().map(|tx| 4 + tx.len()).sum::<usize>()
+    }
+
+    /// Encodes the block with the current version.
+    pub fn encode(&self) -> Result<Vec<u8>, CodecError> {
+        let body_len = self.body_len();
+        let mut out = Vec::with_capacity(self.encoded_len());
+
+        out.push(BLOCK_VERSION_V1);
+        out.extend_from_slice(&len_u32(body_len)?.to_be_bytes());
+        out.extend_from_slice(&self.header.number.to_be_bytes());
+        out.extend_from_slice(&self.header.timestamp.to_be_bytes());
+        out.extend_from_slice(&self.header.parent_hash);
+        out.extend_from_slice(&self.header.state_root);
+        out.extend_from_slice(&len_u32(self.transactions.len())?.to_be_bytes());
+        for tx in &self.transactions {
+            out.extend_from_slice(&len_u32(tx.len())?.to_be_bytes());
+            out.extend_from_slice(tx);
+        }
+
+        Ok(out)
+    }
+
+    /// Decodes a block, rejecting unknown versions, truncated input and trailing bytes.
+    pub fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut reader = Reader(bytes);
+        match reader.u8()? {
+            BLOCK_VERSION_V1 => {}
+            version => return Err(CodecError::UnknownVersion(version)),
+        }
+
+        let body_len = reader.u32()? as usize;
+        let mut body = Reader(reader.take(body_len)?);
+        if !reader.0.is_empty() {
+            return Err(CodecError::TrailingBytes(reader.0.len()));
+        }
+
+        let header = BlockHeader {
+            number: body.u64()?,
+            timestamp: body.u64()?,
+            parent_hash: body.hash()?,
+            state_root: body.hash()?,
+        };
+
+        let tx_count = body.u32()? as usize;
+        // every tx needs at least its length prefix, so bound the allocation by the input
+        let mut transactions = Vec::with_capacity(tx_count.min(body.0.len() / 4));
+        for _ in 0..tx_count {
+            let len = body.u32()? as usize;
+            transactions.push(body.take(len)?.to_vec());
+        }
+        if !body.0.is_empty() {
+            return Err(CodecError::TrailingBytes(body.0.len()));
+        }
+
+        Ok(RollupBlock { header, transactions })
+    }
+}
+
+fn len_u32(len: usize) -> Result<u32, CodecError> {
+    u32::try_from(len).map_err(|_| CodecError::TooLong(len))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        if self.0.len() < n {
+            return Err(CodecError::Truncated { needed: n - self.0.len() });
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        Ok(self.take(N)?.try_into().expect("took exactly N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, CodecError> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, CodecError> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    fn hash(&mut self) -> Result<[u8; 32], CodecError> {
+        self.array()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn sample() -> RollupBlock {
+        RollupBlock {
+            header: BlockHeader {
+                number: 7,
+                timestamp: 1_700_000_000,
+                parent_hash: [0x11; 32],
+                state_root: [0x22; 32],
+            },
+            transactions: vec![b"tx1".to_vec(), Vec::new(), vec![0xff; 300]],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let block = sample();
+        let encoded = block.encode().unwrap();
+        assert_eq!(encoded.len(), block.encoded_len());
+        assert_eq!(encoded[0], BLOCK_VERSION_V1);
+        assert_eq!(RollupBlock::decode(&encoded).unwrap(), block);
+
+        // much smaller than the JSON the poster used to send
+        let json = serde_json::to_vec(&(
+            &block.transactions,
+            hex::encode(block.header.state_root),
+            block.header.number,
+        ))
+        .unwrap();
+        assert!(encoded.len() < json.len());
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        let encoded = sample().encode().unwrap();
+
+        let mut unknown = encoded.clone();
+        unknown[0] = 2;
+        assert_eq!(RollupBlock::decode(&unknown), Err(CodecError::UnknownVersion(2)));
+        assert!(matches!(RollupBlock::decode(&[]), Err(CodecError::Truncated { .. })));
+        assert!(matches!(
+            RollupBlock::decode(&encoded[..encoded.len() - 1]),
+            Err(CodecError::Truncated { needed: 1 })
+        ));
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert_eq!(RollupBlock::decode(&trailing), Err(CodecError::TrailingBytes(1)));
+    }
+
+    fn arb_block() -> impl Strategy<Value = RollupBlock> {
+        (
+            any::<u64>(),
+            any::<u64>(),
+            any::<[u8; 32]>(),
+            any::<[u8; 32]>(),
+            prop::collection::vec(prop::collection::vec(any::<u8>(), 0..512), 0..32),
+        )
+            .prop_map(|(number, timestamp, parent_hash, state_root, transactions)| RollupBlock {
+                header: BlockHeader { number, timestamp, parent_hash, state_root },
+                transactions,
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn fuzz_round_trip(block in arb_block()) {
+            let encoded = block.encode().unwrap();
+            prop_assert_eq!(RollupBlock::decode(&encoded).unwrap(), block);
+        }
+
+        #[test]
+        fn fuzz_decode_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..1024)) {
+            let _ = RollupBlock::decode(&bytes);
+        }
+
+        #[test]
+        fn fuzz_decode_mutated(block in arb_block(), index in any::<prop::sample::Index>(), byte in any::<u8>()) {
+            let mut encoded = block.encode().unwrap();
+            let i = index.index(encoded.len());
+            encoded[i] = byte;
+            if let Ok(decoded) = RollupBlock::decode(&encoded) {
+                // a successful decode must re-encode to the exact input
+                prop_assert_eq!(decoded.encode().unwrap(), encoded);
+            }
+        }
+    }
+}
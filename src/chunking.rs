@@ -0,0 +1,246 @@
+// src/chunking.rs
+//! Splitting payloads that exceed the blob size limit.
+//!
+//! An oversized payload is cut into ordered chunks, each posted as its own
+//! blob (several chunks may share a PayForBlobs). A manifest blob then lists
+//! the chunk spans with their commitments, plus the length and sha256 of the
+//! whole payload, and is what the poster hands back to callers.
+//!
+//! Manifest blobs start with [`MANIFEST_MAGIC`] followed by a version byte
+//! and the JSON encoded [`ChunkManifest`]; chunk blobs start with
+//! [`CHUNK_MAGIC`] and a version byte, so readers walking the namespace can
+//! tell them from payloads of their own. Both magics use the prefix byte
+//! reserved in [`crate::compression`], so no other payload can be mistaken
+//! for either.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+
+use crate::compression::RESERVED_PREFIX;
+use crate::poster::SequenceSpan;
+
+pub const MANIFEST_MAGIC: [u8; 2] = [RESERVED_PREFIX, 0x4d];
+pub const MANIFEST_VERSION_V1: u8 = 1;
+pub const CHUNK_MAGIC: [u8; 2] = [RESERVED_PREFIX, 0x43];
+pub const CHUNK_VERSION_V1: u8 = 1;
+/// Bytes in front of the payload slice in every chunk blob.
+pub const CHUNK_HEADER_LEN: usize = CHUNK_MAGIC.len() + 1;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ChunkError {
+    #[error("unknown chunk manifest version {0}")]
+    UnknownVersion(u8),
+
+    #[error("malformed chunk manifest: {0}")]
+    Malformed(String),
+
+    #[error("chunk {index} has no commitment in the manifest")]
+    MissingCommitment { index: usize },
+
+    #[error("chunk {index} does not match its commitment")]
+    CommitmentMismatch { index: usize },
+
+    #[error("blob referenced as chunk {index} is not a chunk")]
+    NotAChunk { index: usize },
+
+    #[error("expected {expected} chunks, got {actual}")]
+    ChunkCount { expected: usize, actual: usize },
+
+    #[error("reassembled payload is {actual} bytes, manifest says {expected}")]
+    LengthMismatch { expected: u64, actual: u64 },
+
+    #[error("reassembled payload does not match the manifest digest")]
+    DigestMismatch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    /// Length of the reassembled payload in bytes.
+    pub total_len: u64,
+    /// sha256 of the reassembled payload.
+    #[serde(with = "hex::serde")]
+    pub digest: [u8; 32],
+    /// Chunk blobs in payload order.
+    pub chunks: Vec<SequenceSpan>,
+}
+
+impl ChunkManifest {
+    pub fn new(payload: &[u8], chunks: Vec<SequenceSpan>) -> Self {
+        Self {
+            total_len: payload.len() as u64,
+            digest: Sha256::digest(payload).into(),
+            chunks,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = MANIFEST_MAGIC.to_vec();
+        out.push(MANIFEST_VERSION_V1);
+        serde_json::to_writer(&mut out, self).expect("manifest serializes to JSON");
+        out
+    }
+
+    /// Decodes `data` if it is a manifest blob, `Ok(None)` for any other payload.
+    pub fn decode(data: &[u8]) -> Result<Option<Self>, ChunkError> {
+        let Some(rest) = data.strip_prefix(&MANIFEST_MAGIC) else {
+            return Ok(None);
+        };
+        let (&version, body) = rest
+            .split_first()
+            .ok_or_else(|| ChunkError::Malformed("missing version".to_string()))?;
+        if version != MANIFEST_VERSION_V1 {
+            return Err(ChunkError::UnknownVersion(version));
+        }
+
+        let manifest: Self =
+            serde_json::from_slice(body).map_err(|e| ChunkError::Malformed(e.to_string()))?;
+        if let Some(index) = manifest.chunks.iter().position(|span| span.commitment.is_none()) {
+            return Err(ChunkError::MissingCommitment { index });
+        }
+        Ok(Some(manifest))
+    }
+
+    /// Concatenates the payload slices of the chunk blobs and checks them
+    /// against the manifest.
+    ///
+    /// Chunk blobs must already be verified against their commitments.
+    pub fn reassemble(&self, chunks: Vec<Vec<u8>>) -> Result<Vec<u8>, ChunkError> {
+        if chunks.len() != self.chunks.len() {
+            return Err(ChunkError::ChunkCount {
+                expected: self.chunks.len(),
+                actual: chunks.len(),
+            });
+        }
+
+        let mut payload = Vec::with_capacity(usize::try_from(self.total_len).unwrap_or(0));
+        for (index, chunk) in chunks.iter().enumerate() {
+            payload.extend_from_slice(chunk_body(chunk).ok_or(ChunkError::NotAChunk { index })?);
+        }
+        if payload.len() as u64 != self.total_len {
+            return Err(ChunkError::LengthMismatch {
+                expected: self.total_len,
+                actual: payload.len() as u64,
+            });
+        }
+        if <[u8; 32]>::from(Sha256::digest(&payload)) != self.digest {
+            return Err(ChunkError::DigestMismatch);
+        }
+        Ok(payload)
+    }
+}
+
+/// Cuts `payload` into ordered chunk blobs of at most `max_blob_len` bytes,
+/// header included.
+pub fn split(payload: &[u8], max_blob_len: usize) -> Vec<Vec<u8>> {
+    let max_body_len = max_blob_len.saturating_sub(CHUNK_HEADER_LEN).max(1);
+    payload
+        .chunks(max_body_len)
+        .map(|body| [&CHUNK_MAGIC[..], &[CHUNK_VERSION_V1], body].concat())
+        .collect()
+}
+
+/// Whether `data` is a chunk blob, read through its manifest only.
+pub fn is_chunk(data: &[u8]) -> bool {
+    data.starts_with(&CHUNK_MAGIC)
+}
+
+/// The payload slice carried by a chunk blob.
+fn chunk_body(data: &[u8]) -> Option<&[u8]> {
+    match data.strip_prefix(&CHUNK_MAGIC)? {
+        [CHUNK_VERSION_V1, body @ ..] => Some(body),
+        _ => None,
+    }
+}
+
+/// Groups consecutive chunks into PayForBlobs holding at most `max_pfb_len` bytes of data.
+pub fn group_for_pfbs(chunk_lens: &[usize], max_pfb_len: usize) -> Vec<Range<usize>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+
+    for (i, &len) in chunk_lens.iter().enumerate() {
+        if i > start && size + len > max_pfb_len {
+            groups.push(start..i);
+            start = i;
+            size = 0;
+        }
+        size += len;
+    }
+    if start < chunk_lens.len() {
+        groups.push(start..chunk_lens.len());
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::{consts::appconsts::AppVersion, nmt::Namespace, Blob};
+
+    fn manifest_for(payload: &[u8], chunks: &[Vec<u8>]) -> ChunkManifest {
+        let namespace = Namespace::new_v0(b"rollup").unwrap();
+        let spans = chunks
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| SequenceSpan {
+                height: 100 + i as u64,
+                start_index: 0,
+                data_len: 1,
+                commitment: Some(Blob::new(namespace, chunk.clone(), AppVersion::V2).unwrap().commitment),
+            })
+            .collect();
+        ChunkManifest::new(payload, spans)
+    }
+
+    #[test]
+    fn test_split_and_reassemble() {
+        let payload: Vec<u8> = (0..2500u32).map(|i| i as u8).collect();
+        let chunks = split(&payload, 1000);
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![1000, 1000, 509]);
+        assert!(chunks.iter().all(|chunk| is_chunk(chunk)));
+        assert!(!is_chunk(&payload));
+
+        let manifest = manifest_for(&payload, &chunks);
+        let decoded = ChunkManifest::decode(&manifest.encode()).unwrap().unwrap();
+        assert_eq!(decoded, manifest);
+        assert_eq!(decoded.reassemble(chunks.clone()).unwrap(), payload);
+
+        let mut reordered = chunks.clone();
+        reordered.swap(0, 1);
+        assert_eq!(decoded.reassemble(reordered), Err(ChunkError::DigestMismatch));
+        assert!(matches!(
+            decoded.reassemble(chunks[..2].to_vec()),
+            Err(ChunkError::ChunkCount { expected: 3, actual: 2 })
+        ));
+        let mut unmarked = chunks.clone();
+        unmarked[2] = payload[1994..].to_vec();
+        assert_eq!(decoded.reassemble(unmarked), Err(ChunkError::NotAChunk { index: 2 }));
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_manifests() {
+        assert_eq!(ChunkManifest::decode(b"plain payload"), Ok(None));
+
+        let payload = vec![1u8; 10];
+        let mut encoded = manifest_for(&payload, &split(&payload, 4)).encode();
+        encoded[2] = 7;
+        assert_eq!(ChunkManifest::decode(&encoded), Err(ChunkError::UnknownVersion(7)));
+
+        let mut manifest = manifest_for(&payload, &split(&payload, 4));
+        manifest.chunks[1].commitment = None;
+        assert_eq!(
+            ChunkManifest::decode(&manifest.encode()),
+            Err(ChunkError::MissingCommitment { index: 1 })
+        );
+        assert!(matches!(ChunkManifest::decode(&MANIFEST_MAGIC), Err(ChunkError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_group_for_pfbs() {
+        assert_eq!(group_for_pfbs(&[10, 10, 10, 5], 25), vec![0..2, 2..4]);
+        assert_eq!(group_for_pfbs(&[30, 10], 25), vec![0..1, 1..2]);
+        assert_eq!(group_for_pfbs(&[10, 10], 100), vec![0..2]);
+        assert!(group_for_pfbs(&[], 100).is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+// src/compression.rs
+//! Optional compression of blob payloads.
+//!
+//! Compressed payloads start with a 7 byte header so readers can tell them
+//! apart from raw data without any out-of-band configuration:
+//!
+//! ```text
+//! magic          [0xc0, 0x5a]
+//! algorithm      u8    0 = stored, 1 = zstd, 2 = brotli
+//! original_len   u32   big endian, length after decompression
+//! ```
+//!
+//! The first byte `0xc0` is reserved for framing headers (see also
+//! [`crate::chunking`] and [`crate::encryption`]). Payloads written without compression are left
+//! untouched unless they happen to start with it, in which case they are
+//! wrapped as `stored` so decoding stays unambiguous.
+
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::io::Read;
+
+/// Leading byte of every header this crate puts in front of payloads.
+pub const RESERVED_PREFIX: u8 = 0xc0;
+pub const MAGIC: [u8; 2] = [RESERVED_PREFIX, 0x5a];
+pub const HEADER_LEN: usize = 7;
+
+/// Default cap on decompressed payloads.
+pub const DEFAULT_MAX_DECOMPRESSED_LEN: usize = 32 * 1024 * 1024;
+
+const ZSTD_LEVEL: i32 = 9;
+const BROTLI_QUALITY: u32 = 9;
+const BROTLI_WINDOW: u32 = 22;
+const BUFFER_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    #[default]
+    None,
+    Zstd,
+    Brotli,
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Compression::None),
+            "zstd" => Ok(Compression::Zstd),
+            "brotli" => Ok(Compression::Brotli),
+            other => Err(format!("expected none, zstd or brotli, got {other:?}")),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompressionError {
+    #[error("unknown compression algorithm {0}")]
+    UnknownAlgorithm(u8),
+
+    #[error("compressed payload header is truncated")]
+    TruncatedHeader,
+
+    #[error("payload decompresses to {len} bytes, over the limit of {limit}")]
+    TooLarge { len: usize, limit: usize },
+
+    #[error("payload decompressed to {actual} bytes, header says {expected}")]
+    LengthMismatch { expected: usize, actual: usize },
+
+    #[error("compression failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+const STORED: u8 = 0;
+const ZSTD: u8 = 1;
+const BROTLI: u8 = 2;
+
+/// Compresses `data` with `compression`, prefixing the detection header when needed.
+pub fn compress(compression: Compression, data: Vec<u8>) -> Result<Vec<u8>, CompressionError> {
+    let (algorithm, body) = match compression {
+        Compression::None if data.first() != Some(&RESERVED_PREFIX) => return Ok(data),
+        Compression::None => (STORED, Cow::Borrowed(data.as_slice())),
+        Compression::Zstd => (ZSTD, Cow::Owned(zstd::stream::encode_all(data.as_slice(), ZSTD_LEVEL)?)),
+        Compression::Brotli => {
+            let mut out = Vec::new();
+            {
+                let mut writer = brotli::CompressorWriter::new(
+                    &mut out,
+                    BUFFER_SIZE,
+                    BROTLI_QUALITY,
+                    BROTLI_WINDOW,
+                );
+                std::io::Write::write_all(&mut writer, &data)?;
+            }
+            (BROTLI, Cow::Owned(out))
+        }
+    };
+
+    let original_len = u32::try_from(data.len())
+        .map_err(|_| CompressionError::TooLarge { len: data.len(), limit: u32::MAX as usize })?;
+    let mut out = Vec::with_capacity(HEADER_LEN + body.len());
+    out.extend_from_slice(&MAGIC);
+    out.push(algorithm);
+    out.extend_from_slice(&original_len.to_be_bytes());
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+/// Returns the original payload, decompressing it if it carries the header.
+///
+/// Never produces more than `max_len` bytes, whatever the header claims.
+pub fn decompress(data: Vec<u8>, max_len: usize) -> Result<Vec<u8>, CompressionError> {
+    if !data.starts_with(&MAGIC) {
+        if data.len() > max_len {
+            return Err(CompressionError::TooLarge { len: data.len(), limit: max_len });
+        }
+        return Ok(data);
+    }
+    let header = data.get(..HEADER_LEN).ok_or(CompressionError::TruncatedHeader)?;
+    let algorithm = header[2];
+    let expected = u32::from_be_bytes(header[3..7].try_into().expect("4 bytes")) as usize;
+    if expected > max_len {
+        return Err(CompressionError::TooLarge { len: expected, limit: max_len });
+    }
+    let body = &data[HEADER_LEN..];
+
+    let out = match algorithm {
+        STORED => body.to_vec(),
+        ZSTD => read_limited(zstd::stream::read::Decoder::new(body)?, expected)?,
+        BROTLI => read_limited(brotli::Decompressor::new(body, BUFFER_SIZE), expected)?,
+        other => return Err(CompressionError::UnknownAlgorithm(other)),
+    };
+
+    if out.len() != expected {
+        return Err(CompressionError::LengthMismatch { expected, actual: out.len() });
+    }
+    Ok(out)
+}
+
+/// Reads at most one byte past `expected` so oversized streams are caught without inflating them.
+fn read_limited(reader: impl Read, expected: usize) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(expected);
+    reader.take(expected as u64 + 1).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload() -> Vec<u8> {
+        b"rollup block with a lot of repetition ".repeat(200)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for compression in [Compression::Zstd, Compression::Brotli] {
+            let compressed = compress(compression, payload()).unwrap();
+            assert!(compressed.starts_with(&MAGIC));
+            assert!(compressed.len() < payload().len() / 10, "{compression:?} did not shrink");
+            assert_eq!(decompress(compressed, DEFAULT_MAX_DECOMPRESSED_LEN).unwrap(), payload());
+        }
+
+        // raw payloads pass through untouched, unless they look like a header
+        assert_eq!(compress(Compression::None, payload()).unwrap(), payload());
+        assert_eq!(decompress(payload(), DEFAULT_MAX_DECOMPRESSED_LEN).unwrap(), payload());
+        for tricky in [[&MAGIC[..], b"raw"].concat(), vec![RESERVED_PREFIX, 0x4d]] {
+            let stored = compress(Compression::None, tricky.clone()).unwrap();
+            assert_eq!(stored.len(), HEADER_LEN + tricky.len());
+            assert_eq!(decompress(stored, DEFAULT_MAX_DECOMPRESSED_LEN).unwrap(), tricky);
+        }
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let bomb = compress(Compression::Zstd, vec![0; 1 << 20]).unwrap();
+        assert!(matches!(
+            decompress(bomb.clone(), 1024),
+            Err(CompressionError::TooLarge { len: 1048576, limit: 1024 })
+        ));
+
+        // a header understating the size must not let the stream inflate further
+        let mut lying = bomb;
+        lying[3..7].copy_from_slice(&1024u32.to_be_bytes());
+        assert!(matches!(
+            decompress(lying, 2048),
+            Err(CompressionError::LengthMismatch { expected: 1024, actual: 1025 })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_headers() {
+        assert!(matches!(
+            decompress(MAGIC.to_vec(), DEFAULT_MAX_DECOMPRESSED_LEN),
+            Err(CompressionError::TruncatedHeader)
+        ));
+        let unknown = [&MAGIC[..], &[9, 0, 0, 0, 1, 0]].concat();
+        assert!(matches!(
+            decompress(unknown, DEFAULT_MAX_DECOMPRESSED_LEN),
+            Err(CompressionError::UnknownAlgorithm(9))
+        ));
+        assert_eq!("Brotli".parse::<Compression>().unwrap(), Compression::Brotli);
+        assert!("gzip".parse::<Compression>().is_err());
+    }
+}
@@ -0,0 +1,930 @@
+// src/config.rs
+//! Layered runtime configuration.
+//!
+//! Values are resolved in three layers: built-in defaults (a local devnet on
+//! Sepolia), then an optional TOML file, then `VOLITION_*` environment
+//! variables. The result is validated once so every constructor can rely on
+//! well-formed values. Each section only exists when its cargo feature is on.
+
+#[cfg(feature = "l1-settlement")]
+use alloy::primitives::Address;
+#[cfg(feature = "celestia")]
+use crate::compression::{Compression, DEFAULT_MAX_DECOMPRESSED_LEN};
+#[cfg(feature = "celestia")]
+use crate::encryption::{Cipher, MAX_KEY_ID_LEN};
+#[cfg(feature = "celestia")]
+use celestia_types::nmt::Namespace;
+use serde::Deserialize;
+#[cfg(feature = "celestia")]
+use serde::Deserializer;
+#[cfg(feature = "celestia")]
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Environment variable pointing at the TOML config file.
+pub const CONFIG_PATH_ENV: &str = "VOLITION_CONFIG";
+
+#[cfg(feature = "celestia")]
+const DEFAULT_NODE_URL: &str = "ws://localhost:26658";
+#[cfg(feature = "celestia")]
+const DEFAULT_NAMESPACE: &str = "deafbeef";
+/// Stays below the ~1.97 MB a single blob can hold in a 2 MiB transaction.
+#[cfg(feature = "celestia")]
+const DEFAULT_MAX_BLOB_SIZE: usize = 1_900_000;
+/// Comfortably longer than a PFB can sit in the mempool before it is evicted.
+#[cfg(feature = "celestia")]
+pub(crate) const DEFAULT_RECOVERY_WINDOW: u64 = 200;
+#[cfg(feature = "l1-settlement")]
+const DEFAULT_L1_RPC_URL: &str = "https://ethereum-sepolia-rpc.publicnode.com";
+#[cfg(feature = "l1-settlement")]
+const DEFAULT_CONTRACT_ADDRESS: &str = "0x723464397829ce5ccF1AfAb0b49A59e04f299Fc6";
+#[cfg(feature = "l1-settlement")]
+const DEFAULT_PRIVATE_KEY_ENV: &str = "VOLITION_L1_PRIVATE_KEY";
+#[cfg(feature = "l1-settlement")]
+const DEFAULT_KEYSTORE_PASSWORD_ENV: &str = "VOLITION_KEYSTORE_PASSWORD";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("invalid value for `{field}`: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+#[cfg_attr(
+    all(feature = "celestia", feature = "l1-settlement"),
+    serde(deny_unknown_fields)
+)]
+pub struct Config {
+    #[cfg(feature = "celestia")]
+    pub celestia: CelestiaConfig,
+    #[cfg(feature = "l1-settlement")]
+    pub l1: L1Config,
+}
+
+#[cfg(feature = "celestia")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CelestiaConfig {
+    /// celestia-node RPC endpoint (`ws://`, `wss://`, `http://` or `https://`).
+    pub node_url: String,
+    /// JWT issued by `celestia <node-type> auth <level>`.
+    pub auth_token: Option<Secret>,
+    /// Version 0 namespace id, hex encoded (at most 10 bytes).
+    #[serde(deserialize_with = "deserialize_namespace")]
+    pub namespace: Namespace,
+    /// Compression applied to posted payloads; reading detects it on its own.
+    pub compression: Compression,
+    /// Encrypts posted payloads when present; encrypted blobs are always
+    /// decrypted on retrieval if their key is known.
+    pub encryption: Option<EncryptionConfig>,
+    /// Largest payload accepted when decompressing retrieved blobs.
+    pub max_decompressed_len: usize,
+    /// Payloads above this size are split into chunks of at most this many bytes.
+    pub max_blob_size: usize,
+    /// Upper bound on the blob data carried by one PayForBlobs transaction.
+    pub max_pfb_size: usize,
+    pub gas: GasConfig,
+    pub confirmation: ConfirmationConfig,
+    pub follower: FollowerConfig,
+    /// Write-ahead submission queue; disabled when absent.
+    pub queue: Option<QueueConfig>,
+    /// Availability sampling of submitted blobs; disabled when absent.
+    pub sampling: Option<SamplingConfig>,
+}
+
+#[cfg(feature = "celestia")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueueConfig {
+    /// Log file, created on first use.
+    pub path: PathBuf,
+    /// Blocks after the recorded head searched for a pending blob on recovery.
+    #[serde(default = "default_recovery_window")]
+    pub recovery_window: u64,
+}
+
+/// Payload encryption keys, each read from an environment variable.
+#[cfg(feature = "celestia")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptionConfig {
+    /// Cipher for new payloads; retrieval reads it from the envelope.
+    #[serde(default)]
+    pub cipher: Cipher,
+    /// Key new payloads are encrypted with.
+    pub key_id: String,
+    /// Environment variable holding each hex encoded 32 byte key, by key id.
+    /// Retired keys stay listed so older blobs can still be read.
+    pub keys: BTreeMap<String, String>,
+}
+
+/// Gas price policy for PayForBlobs transactions. Prices are in utia per gas unit.
+#[cfg(feature = "celestia")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GasConfig {
+    /// Price of the first attempt.
+    pub price: f64,
+    /// Factor applied to the price after an underpriced or timed out attempt.
+    pub multiplier: f64,
+    /// Hard cap; the poster gives up instead of paying more.
+    pub max_price: f64,
+    pub max_attempts: u32,
+    /// Safety margin applied to the gas estimated from blob sizes.
+    pub gas_adjustment: f64,
+    /// How long a single `blob_submit` may take before it counts as timed out.
+    pub timeout_secs: u64,
+}
+
+/// When a submitted blob counts as final.
+#[cfg(feature = "celestia")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfirmationConfig {
+    /// Headers that must exist on top of the inclusion height.
+    pub depth: u64,
+    /// How often the node's head is polled while waiting for them.
+    pub poll_interval_ms: u64,
+    /// Give up if the depth is not reached within this time after inclusion.
+    pub timeout_secs: u64,
+}
+
+/// How many shares of a confirmed blob are sampled before it counts as available.
+#[cfg(feature = "celestia")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplingConfig {
+    /// Shares that must verify against their row root.
+    pub samples: usize,
+    /// Samples that may fail to fetch or verify before the blob is rejected.
+    pub max_failures: usize,
+}
+
+/// Where the namespace follower starts and how it keeps its place.
+#[cfg(feature = "celestia")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FollowerConfig {
+    /// First Celestia height read when there is no checkpoint yet.
+    pub start_height: u64,
+    /// File recording the last position handed out; without it every run
+    /// starts over at `start_height`.
+    pub checkpoint_path: Option<PathBuf>,
+    /// Head polling interval when the node offers no header subscription.
+    pub poll_interval_ms: u64,
+}
+
+#[cfg(feature = "l1-settlement")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct L1Config {
+    pub rpc_url: String,
+    pub contract_address: Address,
+    pub signer: SignerConfig,
+    pub blobstream: BlobstreamConfig,
+}
+
+/// Where Blobstream data commitments are read from and cached.
+#[cfg(feature = "l1-settlement")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlobstreamConfig {
+    /// Blobstream contract; the settlement contract's `blobstream()` when absent.
+    pub address: Option<Address>,
+    /// L1 block the first scan starts at, e.g. the contract's deployment
+    /// block. Required unless the cache already holds a scan to resume.
+    pub start_block: Option<u64>,
+    /// Commitments seen so far, so restarts do not rescan L1; kept in memory when absent.
+    pub cache_path: Option<PathBuf>,
+    /// Most L1 blocks covered by one `eth_getLogs` request.
+    pub max_block_range: u64,
+    /// How often Blobstream is polled while settlement waits for an attestation.
+    pub poll_interval_ms: u64,
+    /// Give up if the height is not attested within this time.
+    pub attestation_timeout_secs: u64,
+}
+
+/// Backend holding the key that signs settlement transactions.
+#[cfg(feature = "l1-settlement")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SignerConfig {
+    /// Hex encoded private key in an environment variable.
+    Env {
+        #[serde(default = "default_private_key_env")]
+        var: String,
+    },
+    /// Encrypted JSON keystore, unlocked with a password from the environment.
+    Keystore {
+        path: PathBuf,
+        #[serde(default = "default_keystore_password_env")]
+        password_env: String,
+    },
+    /// web3signer-compatible HTTP signer holding the key for `address`.
+    Remote { url: String, address: Address },
+}
+
+/// String that is never printed, for tokens and other credentials.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl Default for CelestiaConfig {
+    fn default() -> Self {
+        Self {
+            node_url: DEFAULT_NODE_URL.to_string(),
+            auth_token: None,
+            namespace: parse_namespace(DEFAULT_NAMESPACE).expect("default namespace is valid"),
+            compression: Compression::default(),
+            encryption: None,
+            max_decompressed_len: DEFAULT_MAX_DECOMPRESSED_LEN,
+            max_blob_size: DEFAULT_MAX_BLOB_SIZE,
+            max_pfb_size: DEFAULT_MAX_BLOB_SIZE,
+            gas: GasConfig::default(),
+            confirmation: ConfirmationConfig::default(),
+            follower: FollowerConfig::default(),
+            queue: None,
+            sampling: None,
+        }
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            // minimum gas price accepted by mainnet validators
+            price: 0.002,
+            multiplier: 1.5,
+            max_price: 0.1,
+            max_attempts: 5,
+            gas_adjustment: 1.1,
+            timeout_secs: 60,
+        }
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl Default for ConfirmationConfig {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            poll_interval_ms: 1_000,
+            timeout_secs: 120,
+        }
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            samples: 16,
+            max_failures: 2,
+        }
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl Default for FollowerConfig {
+    fn default() -> Self {
+        Self {
+            start_height: 1,
+            checkpoint_path: None,
+            poll_interval_ms: 2_000,
+        }
+    }
+}
+
+#[cfg(feature = "l1-settlement")]
+impl Default for L1Config {
+    fn default() -> Self {
+        Self {
+            rpc_url: DEFAULT_L1_RPC_URL.to_string(),
+            contract_address: DEFAULT_CONTRACT_ADDRESS
+                .parse()
+                .expect("default contract address is valid"),
+            signer: SignerConfig::default(),
+            blobstream: BlobstreamConfig::default(),
+        }
+    }
+}
+
+#[cfg(feature = "l1-settlement")]
+impl Default for BlobstreamConfig {
+    fn default() -> Self {
+        Self {
+            address: None,
+            start_block: None,
+            cache_path: None,
+            max_block_range: 10_000,
+            poll_interval_ms: 12_000,
+            attestation_timeout_secs: 7_200,
+        }
+    }
+}
+
+#[cfg(feature = "l1-settlement")]
+impl Default for SignerConfig {
+    fn default() -> Self {
+        SignerConfig::Env {
+            var: default_private_key_env(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from `path`, falling back to `$VOLITION_CONFIG`
+    /// and then to the built-in defaults, and applies environment overrides.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path = path
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from));
+
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None => Self::default(),
+        };
+        config.apply_env(|key| std::env::var(key).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Overrides individual fields from `VOLITION_*` variables looked up through `var`.
+    #[allow(unused_variables)]
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        #[cfg(feature = "celestia")]
+        self.celestia.apply_env(&var)?;
+        #[cfg(feature = "l1-settlement")]
+        self.l1.apply_env(&var)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        #[cfg(feature = "celestia")]
+        self.celestia.validate()?;
+        #[cfg(feature = "l1-settlement")]
+        self.l1.validate()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl CelestiaConfig {
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        if let Some(url) = var("VOLITION_CELESTIA_NODE_URL") {
+            self.node_url = url;
+        }
+        if let Some(token) = var("VOLITION_CELESTIA_AUTH_TOKEN") {
+            self.auth_token = Some(Secret::new(token));
+        }
+        if let Some(namespace) = var("VOLITION_CELESTIA_NAMESPACE") {
+            self.namespace = parse_namespace(&namespace).map_err(|reason| ConfigError::Invalid {
+                field: "celestia.namespace",
+                reason,
+            })?;
+        }
+        if let Some(price) = var("VOLITION_CELESTIA_GAS_PRICE") {
+            self.gas.price = price.parse().map_err(|e| ConfigError::Invalid {
+                field: "celestia.gas.price",
+                reason: format!("{e}"),
+            })?;
+        }
+        if let Some(price) = var("VOLITION_CELESTIA_MAX_GAS_PRICE") {
+            self.gas.max_price = price.parse().map_err(|e| ConfigError::Invalid {
+                field: "celestia.gas.max_price",
+                reason: format!("{e}"),
+            })?;
+        }
+        if let Some(depth) = var("VOLITION_CELESTIA_CONFIRMATIONS") {
+            self.confirmation.depth = depth.parse().map_err(|e| ConfigError::Invalid {
+                field: "celestia.confirmation.depth",
+                reason: format!("{e}"),
+            })?;
+        }
+        if let Some(height) = var("VOLITION_CELESTIA_START_HEIGHT") {
+            self.follower.start_height = height.parse().map_err(|e| ConfigError::Invalid {
+                field: "celestia.follower.start_height",
+                reason: format!("{e}"),
+            })?;
+        }
+        if let Some(path) = var("VOLITION_CELESTIA_CHECKPOINT_PATH") {
+            self.follower.checkpoint_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = var("VOLITION_CELESTIA_QUEUE_PATH") {
+            match &mut self.queue {
+                Some(queue) => queue.path = PathBuf::from(path),
+                None => {
+                    self.queue = Some(QueueConfig {
+                        path: PathBuf::from(path),
+                        recovery_window: default_recovery_window(),
+                    })
+                }
+            }
+        }
+        if let Some(samples) = var("VOLITION_CELESTIA_SAMPLES") {
+            let samples = samples.parse().map_err(|e| ConfigError::Invalid {
+                field: "celestia.sampling.samples",
+                reason: format!("{e}"),
+            })?;
+            self.sampling.get_or_insert_with(SamplingConfig::default).samples = samples;
+        }
+        if let Some(compression) = var("VOLITION_CELESTIA_COMPRESSION") {
+            self.compression = compression.parse().map_err(|reason| ConfigError::Invalid {
+                field: "celestia.compression",
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match self.node_url.split_once("://") {
+            Some(("ws" | "wss" | "http" | "https", rest)) if !rest.is_empty() => {}
+            _ => {
+                return Err(ConfigError::Invalid {
+                    field: "celestia.node_url",
+                    reason: format!(
+                        "expected a ws(s):// or http(s):// url, got {:?}",
+                        self.node_url
+                    ),
+                })
+            }
+        }
+
+        if matches!(&self.auth_token, Some(token) if token.expose().trim().is_empty()) {
+            return Err(ConfigError::Invalid {
+                field: "celestia.auth_token",
+                reason: "token is empty".to_string(),
+            });
+        }
+
+        if let Some(encryption) = &self.encryption {
+            encryption.validate()?;
+        }
+
+        if self.max_decompressed_len == 0 {
+            return Err(ConfigError::Invalid {
+                field: "celestia.max_decompressed_len",
+                reason: "limit must be positive".to_string(),
+            });
+        }
+
+        if self.max_blob_size == 0 {
+            return Err(ConfigError::Invalid {
+                field: "celestia.max_blob_size",
+                reason: "limit must be positive".to_string(),
+            });
+        }
+
+        self.gas.validate()?;
+
+        if self.confirmation.poll_interval_ms == 0 {
+            return Err(ConfigError::Invalid {
+                field: "celestia.confirmation.poll_interval_ms",
+                reason: "interval must be positive".to_string(),
+            });
+        }
+        if self.confirmation.timeout_secs == 0 {
+            return Err(ConfigError::Invalid {
+                field: "celestia.confirmation.timeout_secs",
+                reason: "timeout must be positive".to_string(),
+            });
+        }
+
+        if self.follower.start_height == 0 {
+            return Err(ConfigError::Invalid {
+                field: "celestia.follower.start_height",
+                reason: "Celestia heights start at 1".to_string(),
+            });
+        }
+        if self.follower.poll_interval_ms == 0 {
+            return Err(ConfigError::Invalid {
+                field: "celestia.follower.poll_interval_ms",
+                reason: "interval must be positive".to_string(),
+            });
+        }
+
+        if let Some(queue) = &self.queue {
+            if queue.path.as_os_str().is_empty() {
+                return Err(ConfigError::Invalid {
+                    field: "celestia.queue.path",
+                    reason: "queue path is empty".to_string(),
+                });
+            }
+        }
+
+        if matches!(&self.sampling, Some(sampling) if sampling.samples == 0) {
+            return Err(ConfigError::Invalid {
+                field: "celestia.sampling.samples",
+                reason: "at least one sample is needed; remove the section to disable sampling".to_string(),
+            });
+        }
+
+        if self.max_pfb_size < self.max_blob_size {
+            return Err(ConfigError::Invalid {
+                field: "celestia.max_pfb_size",
+                reason: format!(
+                    "{} is smaller than max_blob_size {}",
+                    self.max_pfb_size, self.max_blob_size
+                ),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl EncryptionConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.key_id.is_empty() || self.key_id.len() > MAX_KEY_ID_LEN {
+            return Err(ConfigError::Invalid {
+                field: "celestia.encryption.key_id",
+                reason: format!("key id must be 1 to {MAX_KEY_ID_LEN} bytes"),
+            });
+        }
+        if !self.keys.contains_key(&self.key_id) {
+            return Err(ConfigError::Invalid {
+                field: "celestia.encryption.keys",
+                reason: format!("no variable given for key {:?}", self.key_id),
+            });
+        }
+        if let Some((key_id, _)) = self.keys.iter().find(|(_, var)| var.is_empty()) {
+            return Err(ConfigError::Invalid {
+                field: "celestia.encryption.keys",
+                reason: format!("variable name for key {key_id:?} is empty"),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl GasConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |field, reason: &str| ConfigError::Invalid {
+            field,
+            reason: reason.to_string(),
+        };
+
+        if !(self.price.is_finite() && self.price > 0.0) {
+            return Err(invalid("celestia.gas.price", "price must be positive"));
+        }
+        if !(self.max_price.is_finite() && self.max_price >= self.price) {
+            return Err(invalid("celestia.gas.max_price", "cap must not be below the initial price"));
+        }
+        if !(self.multiplier.is_finite() && self.multiplier > 1.0) {
+            return Err(invalid("celestia.gas.multiplier", "multiplier must be greater than 1"));
+        }
+        if !(self.gas_adjustment.is_finite() && self.gas_adjustment >= 1.0) {
+            return Err(invalid("celestia.gas.gas_adjustment", "adjustment must be at least 1"));
+        }
+        if self.max_attempts == 0 {
+            return Err(invalid("celestia.gas.max_attempts", "at least one attempt is needed"));
+        }
+        if self.timeout_secs == 0 {
+            return Err(invalid("celestia.gas.timeout_secs", "timeout must be positive"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "l1-settlement")]
+impl L1Config {
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        if let Some(url) = var("VOLITION_L1_RPC_URL") {
+            self.rpc_url = url;
+        }
+        if let Some(address) = var("VOLITION_L1_CONTRACT_ADDRESS") {
+            self.contract_address = address.parse().map_err(|e| ConfigError::Invalid {
+                field: "l1.contract_address",
+                reason: format!("{e}"),
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.rpc_url()?;
+
+        if self.contract_address.is_zero() {
+            return Err(ConfigError::Invalid {
+                field: "l1.contract_address",
+                reason: "zero address".to_string(),
+            });
+        }
+
+        match &self.signer {
+            SignerConfig::Env { var } if var.is_empty() => {
+                return Err(ConfigError::Invalid {
+                    field: "l1.signer.var",
+                    reason: "variable name is empty".to_string(),
+                })
+            }
+            SignerConfig::Keystore { path, .. } if path.as_os_str().is_empty() => {
+                return Err(ConfigError::Invalid {
+                    field: "l1.signer.path",
+                    reason: "keystore path is empty".to_string(),
+                })
+            }
+            SignerConfig::Remote { url, .. } if url.parse::<reqwest::Url>().is_err() => {
+                return Err(ConfigError::Invalid {
+                    field: "l1.signer.url",
+                    reason: format!("{url:?} is not a valid url"),
+                })
+            }
+            _ => {}
+        }
+
+        if self.blobstream.address.is_some_and(|address| address.is_zero()) {
+            return Err(ConfigError::Invalid {
+                field: "l1.blobstream.address",
+                reason: "zero address".to_string(),
+            });
+        }
+        if self.blobstream.max_block_range == 0 {
+            return Err(ConfigError::Invalid {
+                field: "l1.blobstream.max_block_range",
+                reason: "must be at least 1".to_string(),
+            });
+        }
+        if self.blobstream.poll_interval_ms == 0 {
+            return Err(ConfigError::Invalid {
+                field: "l1.blobstream.poll_interval_ms",
+                reason: "interval must be positive".to_string(),
+            });
+        }
+        if self.blobstream.attestation_timeout_secs == 0 {
+            return Err(ConfigError::Invalid {
+                field: "l1.blobstream.attestation_timeout_secs",
+                reason: "timeout must be positive".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The parsed `rpc_url`, which must be http or https.
+    pub fn rpc_url(&self) -> Result<reqwest::Url, ConfigError> {
+        let url = self.rpc_url.parse::<reqwest::Url>().map_err(|e| ConfigError::Invalid {
+            field: "l1.rpc_url",
+            reason: e.to_string(),
+        })?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(ConfigError::Invalid {
+                field: "l1.rpc_url",
+                reason: format!("unsupported scheme {:?}", url.scheme()),
+            });
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(feature = "celestia")]
+fn default_recovery_window() -> u64 {
+    DEFAULT_RECOVERY_WINDOW
+}
+
+#[cfg(feature = "l1-settlement")]
+fn default_private_key_env() -> String {
+    DEFAULT_PRIVATE_KEY_ENV.to_string()
+}
+
+#[cfg(feature = "l1-settlement")]
+fn default_keystore_password_env() -> String {
+    DEFAULT_KEYSTORE_PASSWORD_ENV.to_string()
+}
+
+/// Parses a hex encoded (optionally `0x` prefixed) version 0 namespace id.
+#[cfg(feature = "celestia")]
+pub fn parse_namespace(value: &str) -> Result<Namespace, String> {
+    let hex = value.trim().trim_start_matches("0x");
+    let id = hex::decode(hex).map_err(|e| format!("{value:?} is not valid hex: {e}"))?;
+    Namespace::new_v0(&id).map_err(|e| format!("{value:?} is not a valid v0 namespace: {e}"))
+}
+
+#[cfg(feature = "celestia")]
+fn deserialize_namespace<'de, D>(deserializer: D) -> Result<Namespace, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_namespace(&value).map_err(serde::de::Error::custom)
+}
+
+#[cfg(all(test, feature = "celestia", feature = "l1-settlement"))]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn partial_file_keeps_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+            [celestia]
+            node_url = "wss://mocha.example.org"
+            namespace = "0x0102030405"
+            compression = "zstd"
+
+            [celestia.gas]
+            price = 0.004
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.celestia.node_url, "wss://mocha.example.org");
+        assert_eq!(config.celestia.compression, Compression::Zstd);
+        assert_eq!(config.celestia.max_decompressed_len, DEFAULT_MAX_DECOMPRESSED_LEN);
+        assert_eq!(config.celestia.gas.price, 0.004);
+        assert_eq!(config.celestia.gas.max_attempts, GasConfig::default().max_attempts);
+        assert_eq!(config.celestia.namespace, parse_namespace("0102030405").unwrap());
+        assert_eq!(config.l1.rpc_url, DEFAULT_L1_RPC_URL);
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let vars = HashMap::from([
+            ("VOLITION_CELESTIA_AUTH_TOKEN", "token"),
+            ("VOLITION_L1_RPC_URL", "http://localhost:8545"),
+            ("VOLITION_CELESTIA_COMPRESSION", "brotli"),
+            ("VOLITION_CELESTIA_QUEUE_PATH", "/var/lib/volition/queue.jsonl"),
+            ("VOLITION_CELESTIA_CONFIRMATIONS", "3"),
+            ("VOLITION_CELESTIA_START_HEIGHT", "4200000"),
+            ("VOLITION_CELESTIA_SAMPLES", "32"),
+        ]);
+        let mut config = Config::default();
+        config
+            .apply_env(|key| vars.get(key).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.celestia.auth_token, Some(Secret::new("token")));
+        assert!(!format!("{:?}", config).contains("token\""));
+        assert_eq!(config.l1.rpc_url, "http://localhost:8545");
+        assert_eq!(config.celestia.node_url, DEFAULT_NODE_URL);
+        assert_eq!(config.celestia.compression, Compression::Brotli);
+        assert_eq!(config.celestia.confirmation.depth, 3);
+        assert_eq!(config.celestia.follower.start_height, 4_200_000);
+        assert_eq!(config.celestia.follower.checkpoint_path, None);
+        assert_eq!(config.celestia.sampling, Some(SamplingConfig { samples: 32, max_failures: 2 }));
+        assert_eq!(
+            config.celestia.queue,
+            Some(QueueConfig {
+                path: PathBuf::from("/var/lib/volition/queue.jsonl"),
+                recovery_window: DEFAULT_RECOVERY_WINDOW,
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_values_name_the_field() {
+        let mut config = Config::default();
+        let err = config
+            .apply_env(|key| (key == "VOLITION_CELESTIA_NAMESPACE").then(|| "zz".to_string()))
+            .unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "celestia.namespace", .. }));
+
+        let mut config = Config::default();
+        config.celestia.node_url = "localhost:26658".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "celestia.node_url", .. }));
+
+        let mut config = Config::default();
+        config.celestia.gas.max_price = config.celestia.gas.price / 2.0;
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "celestia.gas.max_price", .. }));
+
+        let mut config = Config::default();
+        config.celestia.max_blob_size = config.celestia.max_pfb_size + 1;
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "celestia.max_pfb_size", .. }));
+
+        let mut config: Config = toml::from_str(
+            r#"
+            [celestia.encryption]
+            cipher = "xchacha20_poly1305"
+            key_id = "2026-10"
+            keys = { "2026-01" = "VOLITION_DATA_KEY_2026_01", "2026-10" = "VOLITION_DATA_KEY_2026_10" }
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        let encryption = config.celestia.encryption.as_mut().unwrap();
+        assert_eq!(encryption.cipher, Cipher::XChaCha20Poly1305);
+        encryption.key_id = "2027-01".to_string();
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "celestia.encryption.keys", .. }));
+
+        let err = toml::from_str::<Config>("[l1]\ncontract_address = \"0x12\"").unwrap_err();
+        assert!(err.to_string().contains("contract_address"));
+
+        let mut config = Config::default();
+        config.l1.signer = SignerConfig::Remote {
+            url: "not a url".to_string(),
+            address: Address::ZERO,
+        };
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "l1.signer.url", .. }));
+
+        // Read without validation, as `from_file` does.
+        let config: Config = toml::from_str("[l1]\nrpc_url = \"ws://localhost:8546\"").unwrap();
+        let err = config.l1.rpc_url().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "l1.rpc_url", .. }));
+    }
+
+    #[test]
+    fn signer_backends_parse() {
+        let config: Config = toml::from_str(
+            r#"
+            [l1.signer]
+            kind = "keystore"
+            path = "/keys/settler.json"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.l1.signer,
+            SignerConfig::Keystore { ref password_env, .. } if password_env == DEFAULT_KEYSTORE_PASSWORD_ENV
+        ));
+
+        let config: Config = toml::from_str(
+            r#"
+            [l1.signer]
+            kind = "remote"
+            url = "http://localhost:9000"
+            address = "0x723464397829ce5ccF1AfAb0b49A59e04f299Fc6"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(config.l1.signer, SignerConfig::Remote { .. }));
+        assert!(matches!(Config::default().l1.signer, SignerConfig::Env { .. }));
+    }
+
+    #[test]
+    fn blobstream_section_parses() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [l1.blobstream]
+            start_block = 5000000
+            cache_path = "/var/lib/volition/blobstream.json"
+            "#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.l1.blobstream.address, None);
+        assert_eq!(config.l1.blobstream.start_block, Some(5_000_000));
+        assert_eq!(Config::default().l1.blobstream.start_block, None);
+        assert_eq!(config.l1.blobstream.max_block_range, BlobstreamConfig::default().max_block_range);
+
+        config.l1.blobstream.max_block_range = 0;
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "l1.blobstream.max_block_range", .. }));
+
+        config.l1.blobstream = BlobstreamConfig { attestation_timeout_secs: 0, ..BlobstreamConfig::default() };
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { field: "l1.blobstream.attestation_timeout_secs", .. }));
+    }
+}
@@ -0,0 +1,130 @@
+// src/confirmation.rs
+//! Confirmation stages of submitted blobs.
+//!
+//! The height `blob_submit` reports is only the node's word that the PFB
+//! landed. The poster hands a span back once two further checks have passed:
+//! the blob can be fetched by its commitment at that height, and the
+//! configured number of headers exist on top of it. With sampling enabled,
+//! random shares of the blob must also verify (see [`crate::sampling`]).
+//! Every transition is published as a [`ConfirmationEvent`] (see
+//! `CelestiaClient::subscribe`).
+
+use celestia_types::Commitment;
+use serde::Serialize;
+
+use crate::poster::SequenceSpan;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum ConfirmationEvent {
+    /// The node accepted the PFB carrying the blob and reported its height.
+    Submitted { height: u64, commitment: Commitment },
+    /// The blob was fetched by commitment at `span.height`.
+    Included { span: SequenceSpan },
+    /// The number of headers on top of the inclusion height changed.
+    Confirming {
+        span: SequenceSpan,
+        confirmations: u64,
+        required: u64,
+    },
+    /// `samples` random shares of the span's rows verified against their row roots.
+    Available { span: SequenceSpan, samples: usize },
+    /// Enough headers exist on top of the span, and it passed sampling if
+    /// enabled; it is returned to the caller.
+    Final { span: SequenceSpan },
+}
+
+impl ConfirmationEvent {
+    /// Celestia height the blob was included at.
+    pub fn height(&self) -> u64 {
+        match self {
+            ConfirmationEvent::Submitted { height, .. } => *height,
+            ConfirmationEvent::Included { span }
+            | ConfirmationEvent::Confirming { span, .. }
+            | ConfirmationEvent::Available { span, .. }
+            | ConfirmationEvent::Final { span } => span.height,
+        }
+    }
+}
+
+/// Counts the headers on top of an inclusion height, reporting each new count once.
+#[derive(Debug, Clone)]
+pub struct DepthTracker {
+    height: u64,
+    required: u64,
+    confirmations: Option<u64>,
+}
+
+impl DepthTracker {
+    pub fn new(height: u64, required: u64) -> Self {
+        Self { height, required, confirmations: None }
+    }
+
+    /// Records the chain head and returns the confirmation count if it changed.
+    ///
+    /// The count never goes down, so a node briefly reporting an older head
+    /// does not undo progress.
+    pub fn observe(&mut self, head: u64) -> Option<u64> {
+        let confirmations = head.saturating_sub(self.height).min(self.required);
+        match self.confirmations {
+            Some(seen) if seen >= confirmations => None,
+            _ => {
+                self.confirmations = Some(confirmations);
+                Some(confirmations)
+            }
+        }
+    }
+
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations.unwrap_or(0)
+    }
+
+    pub fn required(&self) -> u64 {
+        self.required
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.confirmations.is_some_and(|seen| seen >= self.required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_tracker() {
+        let mut tracker = DepthTracker::new(100, 2);
+        assert!(!tracker.is_final());
+
+        // the node may not have synced the inclusion height yet
+        assert_eq!(tracker.observe(99), Some(0));
+        assert_eq!(tracker.observe(100), None);
+        assert_eq!(tracker.observe(101), Some(1));
+        assert_eq!(tracker.observe(100), None);
+        assert!(!tracker.is_final());
+        assert_eq!(tracker.observe(105), Some(2));
+        assert!(tracker.is_final());
+        assert_eq!(tracker.confirmations(), 2);
+
+        let mut tracker = DepthTracker::new(100, 0);
+        assert_eq!(tracker.observe(100), Some(0));
+        assert!(tracker.is_final());
+    }
+
+    #[test]
+    fn test_event_json() {
+        let span = SequenceSpan { height: 7, start_index: 2, data_len: 1, commitment: None };
+        let event = ConfirmationEvent::Confirming { span, confirmations: 1, required: 3 };
+        assert_eq!(event.height(), 7);
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "stage": "confirming",
+                "span": { "height": 7, "start_index": 2, "data_len": 1 },
+                "confirmations": 1,
+                "required": 3,
+            })
+        );
+    }
+}
@@ -0,0 +1,171 @@
+// src/dedup.rs
+//! Local index of submitted blobs by share commitment.
+//!
+//! A blob's commitment is computed locally when it is built, so the poster
+//! knows it before calling `blob_submit`. The index remembers which
+//! commitments were sent and where they landed: a blob that is already
+//! included is not paid for again, and one whose earlier submission has an
+//! unknown outcome is first looked for on chain. The index lives in memory;
+//! commitments it does not know, after a restart or once evicted, are only
+//! looked for on chain when the submission queue holds them as pending, so a
+//! new blob costs no lookups.
+//!
+//! Sealing draws a fresh nonce, so an encrypted payload would get a new
+//! commitment on every attempt. [`SealedPayloads`] keeps the sealed bytes of
+//! recent payloads, and a retry posts exactly the blob that was sent before.
+//! After a restart the same bytes are taken from the pending queue entry.
+
+use celestia_types::Commitment;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::encryption::Cipher;
+
+/// What is known about a commitment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Submission {
+    /// Sent with the chain head at `from_height`, outcome unknown.
+    Pending { from_height: u64 },
+    /// Included at `height`.
+    Included { height: u64 },
+}
+
+/// Bounded map from commitment to [`Submission`], forgetting the oldest entries first.
+#[derive(Debug)]
+pub struct SubmissionIndex {
+    capacity: usize,
+    entries: BTreeMap<Commitment, Submission>,
+    order: VecDeque<Commitment>,
+}
+
+impl SubmissionIndex {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: BTreeMap::new(), order: VecDeque::new() }
+    }
+
+    pub fn get(&self, commitment: &Commitment) -> Option<Submission> {
+        self.entries.get(commitment).copied()
+    }
+
+    /// Records an attempt to submit `commitment`.
+    ///
+    /// Earlier attempts may still land, so the oldest `from_height` is kept,
+    /// and an inclusion that is already known is never downgraded.
+    pub fn record_pending(&mut self, commitment: Commitment, from_height: u64) {
+        let entry = match self.get(&commitment) {
+            Some(Submission::Included { .. }) => return,
+            Some(Submission::Pending { from_height: earlier }) => {
+                Submission::Pending { from_height: earlier.min(from_height) }
+            }
+            None => Submission::Pending { from_height },
+        };
+        self.insert(commitment, entry);
+    }
+
+    pub fn record_included(&mut self, commitment: Commitment, height: u64) {
+        self.insert(commitment, Submission::Included { height });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn insert(&mut self, commitment: Commitment, submission: Submission) {
+        if self.entries.insert(commitment, submission).is_none() {
+            self.order.push_back(commitment);
+        }
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self.order.pop_front() else { break };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Bounded map from payload to its sealed bytes, forgetting the oldest entries first.
+#[derive(Debug)]
+pub struct SealedPayloads {
+    capacity: usize,
+    entries: BTreeMap<[u8; 32], Vec<u8>>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl SealedPayloads {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: BTreeMap::new(), order: VecDeque::new() }
+    }
+
+    /// Key of `payload` sealed with `cipher` under `key_id`; a rotated key
+    /// or another cipher seals anew.
+    pub fn key(cipher: Cipher, key_id: &str, payload: &[u8]) -> [u8; 32] {
+        Sha256::new()
+            .chain_update([cipher.id()])
+            .chain_update((key_id.len() as u64).to_be_bytes())
+            .chain_update(key_id)
+            .chain_update(payload)
+            .finalize()
+            .into()
+    }
+
+    pub fn get(&self, key: &[u8; 32]) -> Option<&[u8]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    pub fn insert(&mut self, key: [u8; 32], sealed: Vec<u8>) {
+        if self.entries.insert(key, sealed).is_none() {
+            self.order.push_back(key);
+        }
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self.order.pop_front() else { break };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commitment(byte: u8) -> Commitment {
+        Commitment([byte; 32])
+    }
+
+    #[test]
+    fn test_submission_index() {
+        let mut index = SubmissionIndex::new(2);
+        index.record_pending(commitment(1), 10);
+        index.record_pending(commitment(1), 12);
+        assert_eq!(index.get(&commitment(1)), Some(Submission::Pending { from_height: 10 }));
+
+        index.record_included(commitment(1), 13);
+        index.record_pending(commitment(1), 20);
+        assert_eq!(index.get(&commitment(1)), Some(Submission::Included { height: 13 }));
+
+        // the oldest commitment is forgotten first
+        index.record_pending(commitment(2), 20);
+        index.record_pending(commitment(3), 21);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.get(&commitment(1)), None);
+        assert_eq!(index.get(&commitment(3)), Some(Submission::Pending { from_height: 21 }));
+    }
+
+    #[test]
+    fn test_sealed_payloads() {
+        let mut sealed = SealedPayloads::new(2);
+        let key = |key_id: &str, payload: &[u8]| SealedPayloads::key(Cipher::Aes256Gcm, key_id, payload);
+        let block = key("k1", b"block");
+        assert_ne!(block, key("k2", b"block"));
+        assert_ne!(block, SealedPayloads::key(Cipher::XChaCha20Poly1305, "k1", b"block"));
+        // the key id length is hashed, so ids cannot borrow payload bytes
+        assert_ne!(key("k", b"1block"), block);
+
+        sealed.insert(block, vec![1]);
+        sealed.insert(key("k1", b"other"), vec![2]);
+        assert_eq!(sealed.get(&block), Some(&[1][..]));
+        sealed.insert(key("k1", b"third"), vec![3]);
+        assert_eq!(sealed.get(&block), None);
+    }
+}
@@ -0,0 +1,372 @@
+// src/encryption.rs
+//! Optional encryption of blob payloads for rollups that cannot publish
+//! plaintext.
+//!
+//! Encrypted payloads are wrapped in an envelope naming the cipher and the
+//! key, so readers pick the right key without out-of-band configuration and
+//! keys can be rotated without losing access to older blobs:
+//!
+//! ```text
+//! magic       [0xc0, 0x45]
+//! version     u8    1
+//! cipher      u8    1 = aes-256-gcm, 2 = xchacha20-poly1305
+//! key_id_len  u8
+//! key_id      key_id_len bytes of UTF-8
+//! nonce       12 bytes (aes-256-gcm) or 24 bytes (xchacha20-poly1305)
+//! ciphertext  followed by the 16 byte authentication tag
+//! ```
+//!
+//! Everything before the ciphertext is authenticated as associated data.
+//! Payloads are compressed before they are sealed and chunked afterwards, so
+//! the blobs, their commitments and the proofs built from their shares all
+//! cover the ciphertext that was posted.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::Aes256Gcm;
+use async_trait::async_trait;
+use chacha20poly1305::XChaCha20Poly1305;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::compression::RESERVED_PREFIX;
+use crate::config::EncryptionConfig;
+
+pub const ENVELOPE_MAGIC: [u8; 2] = [RESERVED_PREFIX, 0x45];
+pub const ENVELOPE_VERSION_V1: u8 = 1;
+pub const KEY_LEN: usize = 32;
+/// Key ids are stored behind a one byte length.
+pub const MAX_KEY_ID_LEN: usize = u8::MAX as usize;
+
+const AES_256_GCM: u8 = 1;
+const XCHACHA20_POLY1305: u8 = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cipher {
+    #[default]
+    Aes256Gcm,
+    #[serde(rename = "xchacha20_poly1305")]
+    XChaCha20Poly1305,
+}
+
+impl Cipher {
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            Cipher::Aes256Gcm => AES_256_GCM,
+            Cipher::XChaCha20Poly1305 => XCHACHA20_POLY1305,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self, EncryptionError> {
+        match id {
+            AES_256_GCM => Ok(Cipher::Aes256Gcm),
+            XCHACHA20_POLY1305 => Ok(Cipher::XChaCha20Poly1305),
+            other => Err(EncryptionError::UnknownCipher(other)),
+        }
+    }
+
+    fn nonce_len(self) -> usize {
+        match self {
+            Cipher::Aes256Gcm => 12,
+            Cipher::XChaCha20Poly1305 => 24,
+        }
+    }
+}
+
+impl std::str::FromStr for Cipher {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "aes256_gcm" => Ok(Cipher::Aes256Gcm),
+            "xchacha20_poly1305" => Ok(Cipher::XChaCha20Poly1305),
+            other => Err(format!("expected aes256_gcm or xchacha20_poly1305, got {other:?}")),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("environment variable {var} for key {key_id:?} is not set")]
+    MissingEnv { key_id: String, var: String },
+
+    #[error("key {0:?} is not 32 hex encoded bytes")]
+    InvalidKey(String),
+
+    #[error("no key with id {0:?}")]
+    UnknownKey(String),
+
+    #[error("key id of {0} bytes is longer than {MAX_KEY_ID_LEN}")]
+    KeyIdTooLong(usize),
+
+    #[error("unknown encryption envelope version {0}")]
+    UnknownVersion(u8),
+
+    #[error("unknown cipher {0}")]
+    UnknownCipher(u8),
+
+    #[error("encryption envelope is truncated")]
+    Truncated,
+
+    #[error("payload is encrypted with key {0:?} but no keys are configured")]
+    NoKeyProvider(String),
+
+    /// Wrong key, or the envelope was modified after sealing.
+    #[error("payload encrypted with key {0:?} failed authentication")]
+    Decrypt(String),
+
+    #[error("key provider failed: {0}")]
+    Provider(String),
+}
+
+/// 256 bit key for either cipher. `Debug` never shows the key bytes.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DataKey([u8; KEY_LEN]);
+
+impl DataKey {
+    pub fn new(bytes: [u8; KEY_LEN]) -> Self {
+        Self(bytes)
+    }
+
+    /// Parses a hex encoded (optionally `0x` prefixed) key.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let mut bytes = [0; KEY_LEN];
+        hex::decode_to_slice(hex.trim().trim_start_matches("0x"), &mut bytes).ok()?;
+        Some(Self(bytes))
+    }
+}
+
+impl fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DataKey(<redacted>)")
+    }
+}
+
+/// Source of the keys payloads are sealed and opened with.
+///
+/// Implementations may fetch keys from a KMS; the poster asks for a key once
+/// per payload and does not cache it.
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    /// Id of the key new payloads are encrypted with.
+    async fn current_key_id(&self) -> Result<String, EncryptionError>;
+
+    async fn key(&self, key_id: &str) -> Result<DataKey, EncryptionError>;
+}
+
+/// Builds the key provider described in the config.
+pub fn from_config(config: &EncryptionConfig) -> Result<Box<dyn KeyProvider>, EncryptionError> {
+    let keys = KeyRing::from_env(config)?;
+    tracing::info!(
+        "Encrypting payloads with {:?} under key {:?} ({} keys loaded)",
+        config.cipher,
+        config.key_id,
+        config.keys.len()
+    );
+    Ok(Box::new(keys))
+}
+
+/// Fixed set of keys held in memory.
+#[derive(Debug, Clone)]
+pub struct KeyRing {
+    current: String,
+    keys: HashMap<String, DataKey>,
+}
+
+impl KeyRing {
+    /// `current` must be one of `keys`.
+    pub fn new(current: impl Into<String>, keys: HashMap<String, DataKey>) -> Result<Self, EncryptionError> {
+        let current = current.into();
+        if current.len() > MAX_KEY_ID_LEN {
+            return Err(EncryptionError::KeyIdTooLong(current.len()));
+        }
+        if !keys.contains_key(&current) {
+            return Err(EncryptionError::UnknownKey(current));
+        }
+        Ok(Self { current, keys })
+    }
+
+    /// Reads every key in `config.keys` from its environment variable.
+    pub fn from_env(config: &EncryptionConfig) -> Result<Self, EncryptionError> {
+        let keys = config
+            .keys
+            .iter()
+            .map(|(key_id, var)| {
+                let hex = std::env::var(var).map_err(|_| EncryptionError::MissingEnv {
+                    key_id: key_id.clone(),
+                    var: var.clone(),
+                })?;
+                let key = DataKey::from_hex(&hex).ok_or_else(|| EncryptionError::InvalidKey(key_id.clone()))?;
+                Ok((key_id.clone(), key))
+            })
+            .collect::<Result<_, EncryptionError>>()?;
+        Self::new(config.key_id.clone(), keys)
+    }
+}
+
+#[async_trait]
+impl KeyProvider for KeyRing {
+    async fn current_key_id(&self) -> Result<String, EncryptionError> {
+        Ok(self.current.clone())
+    }
+
+    async fn key(&self, key_id: &str) -> Result<DataKey, EncryptionError> {
+        self.keys
+            .get(key_id)
+            .cloned()
+            .ok_or_else(|| EncryptionError::UnknownKey(key_id.to_string()))
+    }
+}
+
+/// Encrypts `plaintext` under `key` and wraps it in an envelope naming `key_id`.
+pub fn seal(cipher: Cipher, key_id: &str, key: &DataKey, plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let key_id_len = u8::try_from(key_id.len()).map_err(|_| EncryptionError::KeyIdTooLong(key_id.len()))?;
+    let mut out = ENVELOPE_MAGIC.to_vec();
+    out.extend_from_slice(&[ENVELOPE_VERSION_V1, cipher.id(), key_id_len]);
+    out.extend_from_slice(key_id.as_bytes());
+
+    let ciphertext = match cipher {
+        Cipher::Aes256Gcm => {
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            out.extend_from_slice(&nonce);
+            Aes256Gcm::new(&key.0.into()).encrypt(&nonce, Payload { msg: plaintext, aad: &out })
+        }
+        Cipher::XChaCha20Poly1305 => {
+            let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+            out.extend_from_slice(&nonce);
+            XChaCha20Poly1305::new(&key.0.into()).encrypt(&nonce, Payload { msg: plaintext, aad: &out })
+        }
+    }
+    .expect("in-memory encryption does not fail");
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// An encrypted payload, borrowed from the blob data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope<'a> {
+    pub cipher: Cipher,
+    pub key_id: &'a str,
+    /// Envelope bytes up to and including the nonce, authenticated as associated data.
+    header: &'a [u8],
+    nonce: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+impl<'a> Envelope<'a> {
+    /// Parses the envelope at the start of `data`, `None` if it is not encrypted.
+    pub fn parse(data: &'a [u8]) -> Result<Option<Self>, EncryptionError> {
+        let Some(rest) = data.strip_prefix(&ENVELOPE_MAGIC) else {
+            return Ok(None);
+        };
+        let [version, cipher, key_id_len, rest @ ..] = rest else {
+            return Err(EncryptionError::Truncated);
+        };
+        if *version != ENVELOPE_VERSION_V1 {
+            return Err(EncryptionError::UnknownVersion(*version));
+        }
+        let cipher = Cipher::from_id(*cipher)?;
+
+        let key_id_len = *key_id_len as usize;
+        let nonce_len = cipher.nonce_len();
+        if rest.len() < key_id_len + nonce_len {
+            return Err(EncryptionError::Truncated);
+        }
+        let key_id = std::str::from_utf8(&rest[..key_id_len]).map_err(|_| EncryptionError::Truncated)?;
+        let nonce = &rest[key_id_len..key_id_len + nonce_len];
+        let header_len = ENVELOPE_MAGIC.len() + 3 + key_id_len + nonce_len;
+
+        Ok(Some(Self {
+            cipher,
+            key_id,
+            header: &data[..header_len],
+            nonce,
+            ciphertext: &data[header_len..],
+        }))
+    }
+
+    /// Decrypts and authenticates the payload.
+    pub fn open(&self, key: &DataKey) -> Result<Vec<u8>, EncryptionError> {
+        let payload = Payload { msg: self.ciphertext, aad: self.header };
+        match self.cipher {
+            Cipher::Aes256Gcm => Aes256Gcm::new(&key.0.into()).decrypt(self.nonce.into(), payload),
+            Cipher::XChaCha20Poly1305 => XChaCha20Poly1305::new(&key.0.into()).decrypt(self.nonce.into(), payload),
+        }
+        .map_err(|_| EncryptionError::Decrypt(self.key_id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring() -> KeyRing {
+        let keys = HashMap::from([
+            ("2026-01".to_string(), DataKey::new([1; KEY_LEN])),
+            ("2026-10".to_string(), DataKey::new([2; KEY_LEN])),
+        ]);
+        KeyRing::new("2026-10", keys).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_seal_and_open() {
+        let keys = ring();
+        let plaintext = b"private rollup block".to_vec();
+
+        for cipher in [Cipher::Aes256Gcm, Cipher::XChaCha20Poly1305] {
+            let key_id = keys.current_key_id().await.unwrap();
+            let sealed = seal(cipher, &key_id, &keys.key(&key_id).await.unwrap(), &plaintext).unwrap();
+            assert!(sealed.starts_with(&ENVELOPE_MAGIC));
+            assert_eq!(sealed.len(), 5 + key_id.len() + cipher.nonce_len() + plaintext.len() + 16);
+
+            let envelope = Envelope::parse(&sealed).unwrap().unwrap();
+            assert_eq!((envelope.cipher, envelope.key_id), (cipher, "2026-10"));
+            assert_eq!(envelope.open(&keys.key("2026-10").await.unwrap()).unwrap(), plaintext);
+
+            // the wrong key or a modified key id must not decrypt
+            let wrong = keys.key("2026-01").await.unwrap();
+            assert!(matches!(envelope.open(&wrong), Err(EncryptionError::Decrypt(_))));
+            let mut relabeled = sealed.clone();
+            relabeled[5..12].copy_from_slice(b"2026-01");
+            let envelope = Envelope::parse(&relabeled).unwrap().unwrap();
+            assert!(matches!(envelope.open(&wrong), Err(EncryptionError::Decrypt(_))));
+        }
+
+        // fresh nonces: sealing twice gives different ciphertexts
+        let key = DataKey::new([2; KEY_LEN]);
+        let sealed = || seal(Cipher::Aes256Gcm, "k", &key, &plaintext).unwrap();
+        assert_ne!(sealed(), sealed());
+    }
+
+    #[test]
+    fn test_rejects_bad_envelopes() {
+        assert_eq!(Envelope::parse(b"plain payload").unwrap(), None);
+        assert!(matches!(Envelope::parse(&ENVELOPE_MAGIC), Err(EncryptionError::Truncated)));
+        assert!(matches!(
+            Envelope::parse(&[&ENVELOPE_MAGIC[..], &[2, 1, 0]].concat()),
+            Err(EncryptionError::UnknownVersion(2))
+        ));
+        assert!(matches!(
+            Envelope::parse(&[&ENVELOPE_MAGIC[..], &[1, 7, 0]].concat()),
+            Err(EncryptionError::UnknownCipher(7))
+        ));
+        // header fits, nonce does not
+        assert!(matches!(
+            Envelope::parse(&[&ENVELOPE_MAGIC[..], &[1, 1, 1, b'k'], &[0; 11]].concat()),
+            Err(EncryptionError::Truncated)
+        ));
+
+        assert!(matches!(
+            KeyRing::new("missing", HashMap::new()),
+            Err(EncryptionError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            seal(Cipher::Aes256Gcm, &"k".repeat(256), &DataKey::new([0; KEY_LEN]), b""),
+            Err(EncryptionError::KeyIdTooLong(256))
+        ));
+        assert_eq!(DataKey::from_hex(&format!("0x{}", "ab".repeat(32))), Some(DataKey::new([0xab; KEY_LEN])));
+        assert_eq!(DataKey::from_hex("abcd"), None);
+        assert_eq!("XChaCha20_Poly1305".parse::<Cipher>().unwrap(), Cipher::XChaCha20Poly1305);
+    }
+}
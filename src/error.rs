@@ -0,0 +1,266 @@
+// src/error.rs
+//! Error type shared by the poster, prover and settlement paths.
+//!
+//! Variants are split by failure source so callers can decide whether to
+//! retry with [`Error::is_retryable`] instead of matching on messages.
+
+#[cfg(feature = "celestia")]
+use celestia_types::{nmt::Namespace, Commitment};
+
+use crate::block::CodecError;
+use crate::config::ConfigError;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    #[error(transparent)]
+    Codec(#[from] CodecError),
+
+    #[cfg(feature = "celestia")]
+    #[error(transparent)]
+    Compression(#[from] crate::compression::CompressionError),
+
+    #[cfg(feature = "celestia")]
+    #[error(transparent)]
+    Encryption(#[from] crate::encryption::EncryptionError),
+
+    #[cfg(feature = "celestia")]
+    #[error(transparent)]
+    Chunking(#[from] crate::chunking::ChunkError),
+
+    #[cfg(feature = "celestia")]
+    #[error(transparent)]
+    Queue(#[from] crate::queue::QueueError),
+
+    #[cfg(feature = "celestia")]
+    #[error(transparent)]
+    Checkpoint(#[from] crate::follower::CheckpointError),
+
+    /// `blob_submit` did not answer within the configured timeout.
+    #[cfg(feature = "celestia")]
+    #[error("blob submission timed out (attempt {attempts} at {gas_price} utia/gas)")]
+    SubmitTimeout { attempts: u32, gas_price: f64 },
+
+    /// The chain did not grow `required` headers past a blob's height in time.
+    #[cfg(feature = "celestia")]
+    #[error("blob at height {height} has {confirmations} of {required} confirmations after the timeout")]
+    ConfirmationTimeout { height: u64, confirmations: u64, required: u64 },
+
+    /// Too few random shares of a span could be fetched and verified.
+    #[cfg(feature = "celestia")]
+    #[error("span {span} failed availability sampling: {passed} of {required} samples verified, {} failed", .failures.len())]
+    Unavailable {
+        span: crate::poster::SequenceSpan,
+        passed: usize,
+        required: usize,
+        failures: Vec<crate::sampling::SampleFailure>,
+    },
+
+    /// A payload exceeds the blob size limit where chunking is not available.
+    #[error("payload of {len} bytes exceeds the blob limit of {limit} bytes")]
+    PayloadTooLarge { len: usize, limit: usize },
+
+    /// celestia-node could not be reached or rejected the request.
+    #[cfg(feature = "celestia")]
+    #[error("celestia rpc error: {0}")]
+    CelestiaRpc(#[from] celestia_rpc::Error),
+
+    /// A blob or share failed local validation.
+    #[cfg(feature = "celestia")]
+    #[error("invalid celestia data: {0}")]
+    CelestiaTypes(#[from] celestia_types::Error),
+
+    #[cfg(feature = "celestia")]
+    #[error("no data found for namespace {namespace:?} at height {height}")]
+    NamespaceNotFound { height: u64, namespace: Namespace },
+
+    /// The backend has no block at `height` yet.
+    #[cfg(feature = "celestia")]
+    #[error("height {height} is above the head {head}")]
+    HeightUnavailable { height: u64, head: u64 },
+
+    /// A submitted blob is missing from the block it was reported in.
+    #[cfg(feature = "celestia")]
+    #[error("blob {commitment:?} not found at height {height}")]
+    BlobNotFound { height: u64, commitment: Commitment },
+
+    /// The node served other data than was submitted under a blob's commitment.
+    #[cfg(feature = "celestia")]
+    #[error("blob {commitment:?} at height {height} does not hold the submitted data")]
+    DataMismatch { height: u64, commitment: Commitment },
+
+    /// The shares a span points at do not hold the blob it describes.
+    #[cfg(feature = "celestia")]
+    #[error("span {span} does not match the chain: {reason}")]
+    SpanMismatch {
+        span: crate::poster::SequenceSpan,
+        reason: String,
+    },
+
+    #[error("failed to construct proof: {0}")]
+    Proof(String),
+
+    /// The L1 node could not be reached or returned an unexpected response.
+    #[cfg(feature = "l1-settlement")]
+    #[error("l1 rpc error: {0}")]
+    L1Rpc(#[source] alloy::transports::TransportError),
+
+    /// `eth_call` or gas estimation of the settlement transaction reverted.
+    #[cfg(feature = "l1-settlement")]
+    #[error("settlement simulation reverted: {message}")]
+    SimulationReverted {
+        message: String,
+        data: Option<String>,
+    },
+
+    /// Waiting for the receipt of a sent transaction failed.
+    #[cfg(feature = "l1-settlement")]
+    #[error("failed to get receipt for {tx_hash}: {source}")]
+    Receipt {
+        tx_hash: alloy::primitives::TxHash,
+        source: alloy_provider::PendingTransactionError,
+    },
+
+    #[cfg(feature = "l1-settlement")]
+    #[error(transparent)]
+    Signer(#[from] crate::signer::SignerError),
+
+    #[cfg(feature = "l1-settlement")]
+    #[error(transparent)]
+    Blobstream(#[from] crate::blobstream::BlobstreamError),
+}
+
+impl Error {
+    /// Whether the same request may succeed if sent again later.
+    ///
+    /// Transport failures, timeouts and rate limits are retryable; reverts,
+    /// missing data, invalid input and configuration errors are not. Neither
+    /// is a lost receipt: the transaction was already broadcast, see
+    /// [`Error::unresolved_tx`].
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "celestia")]
+            Error::CelestiaRpc(celestia_rpc::Error::JsonRpc(e)) => {
+                use jsonrpsee::core::ClientError;
+                matches!(
+                    e,
+                    ClientError::Transport(_)
+                        | ClientError::RestartNeeded(_)
+                        | ClientError::RequestTimeout
+                )
+            }
+            #[cfg(feature = "celestia")]
+            Error::SubmitTimeout { .. } | Error::HeightUnavailable { .. } => true,
+            #[cfg(feature = "l1-settlement")]
+            Error::L1Rpc(e) => is_retryable_transport_error(e),
+            #[cfg(feature = "l1-settlement")]
+            Error::Signer(crate::signer::SignerError::Remote(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Transaction that was sent but whose outcome is unknown.
+    ///
+    /// Sending it again may settle twice or clash on the nonce; the caller
+    /// has to look the hash up on L1 first.
+    #[cfg(feature = "l1-settlement")]
+    pub fn unresolved_tx(&self) -> Option<alloy::primitives::TxHash> {
+        match self {
+            Error::Receipt { tx_hash, .. } => Some(*tx_hash),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "celestia")]
+impl From<jsonrpsee::core::ClientError> for Error {
+    fn from(e: jsonrpsee::core::ClientError) -> Self {
+        Error::CelestiaRpc(e.into())
+    }
+}
+
+/// Splits L1 call failures into reverts and transport errors.
+#[cfg(feature = "l1-settlement")]
+impl From<alloy::transports::TransportError> for Error {
+    fn from(e: alloy::transports::TransportError) -> Self {
+        match e.as_error_resp() {
+            // geth and most clients report reverts as code 3 or with an "execution reverted" message
+            Some(payload) if payload.code == 3 || payload.message.contains("revert") => {
+                Error::SimulationReverted {
+                    message: payload.message.to_string(),
+                    data: payload
+                        .data
+                        .as_ref()
+                        .map(|data| data.get().trim_matches('"').to_string()),
+                }
+            }
+            _ => Error::L1Rpc(e),
+        }
+    }
+}
+
+#[cfg(feature = "l1-settlement")]
+fn is_retryable_transport_error(e: &alloy::transports::TransportError) -> bool {
+    use alloy::transports::RpcError;
+    match e {
+        RpcError::Transport(_) | RpcError::NullResp => true,
+        RpcError::ErrorResp(payload) => payload.is_retry_err(),
+        _ => false,
+    }
+}
+
+#[cfg(all(test, feature = "l1-settlement"))]
+mod tests {
+    use super::*;
+    use alloy::transports::{TransportError, TransportErrorKind};
+
+    #[test]
+    fn test_revert_is_not_retryable() {
+        let err = Error::from(TransportError::ErrorResp(
+            serde_json::from_str(
+                r#"{"code":3,"message":"execution reverted: Invalid span or height","data":"0x08c379a0"}"#,
+            )
+            .unwrap(),
+        ));
+
+        assert!(matches!(err, Error::SimulationReverted { ref data, .. } if data.as_deref() == Some("0x08c379a0")));
+        assert!(!err.is_retryable());
+        assert_eq!(err.unresolved_tx(), None);
+
+        // once the transaction is out, a lost receipt must not lead to a resend
+        use alloy_provider::{PendingTransactionError, WatchTxError};
+        let tx_hash = alloy::primitives::TxHash::repeat_byte(1);
+        for source in [
+            PendingTransactionError::TxWatcher(WatchTxError::Timeout),
+            PendingTransactionError::FailedToRegister,
+            PendingTransactionError::TransportError(TransportErrorKind::backend_gone()),
+        ] {
+            let err = Error::Receipt { tx_hash, source };
+            assert!(!err.is_retryable());
+            assert_eq!(err.unresolved_tx(), Some(tx_hash));
+        }
+    }
+
+    #[test]
+    fn test_transport_errors_are_retryable() {
+        let err = Error::from(TransportErrorKind::backend_gone());
+        assert!(matches!(err, Error::L1Rpc(_)));
+        assert!(err.is_retryable());
+
+        #[cfg(feature = "celestia")]
+        {
+            let err = Error::from(jsonrpsee::core::ClientError::RequestTimeout);
+            assert!(err.is_retryable());
+
+            let err = Error::from(jsonrpsee::core::ClientError::Custom("blob: not found".into()));
+            assert!(!err.is_retryable());
+        }
+
+        let err = Error::Proof("no shares".into());
+        assert!(!err.is_retryable());
+    }
+}
@@ -0,0 +1,142 @@
+// src/fees.rs
+//! Gas estimation and price escalation for PayForBlobs transactions.
+
+use celestia_types::{
+    consts::appconsts::{self, SHARE_SIZE},
+    Blob, TxConfig,
+};
+use std::time::Duration;
+
+use crate::config::GasConfig;
+use crate::error::Error;
+use crate::span::blob_share_count;
+
+/// Gas charged for every PFB regardless of its blobs (`PFBGasFixedCost` in celestia-app).
+const PFB_GAS_FIXED_COST: u64 = 75_000;
+/// Bytes of transaction data per blob (`BytesPerBlobInfo` in celestia-app).
+const BYTES_PER_BLOB_INFO: u64 = 70;
+
+/// Gas a PFB carrying `blobs` needs, mirroring celestia-app's `DefaultEstimateGas`.
+pub fn estimate_gas(blobs: &[Blob]) -> u64 {
+    let shares: u64 = blobs.iter().map(|blob| blob_share_count(blob.data.len())).sum();
+    shares * SHARE_SIZE as u64 * appconsts::v3::GAS_PER_BLOB_BYTE
+        + appconsts::v3::TX_SIZE_COST_PER_BYTE * BYTES_PER_BLOB_INFO * blobs.len() as u64
+        + PFB_GAS_FIXED_COST
+}
+
+/// Fee in utia for `gas` at `gas_price`, as deducted by the chain.
+///
+/// Cosmos charges the full gas limit, not the gas used, so this is what a
+/// successful submission actually paid.
+pub fn fee(gas: u64, gas_price: f64) -> u64 {
+    (gas as f64 * gas_price).ceil() as u64
+}
+
+/// Whether the node rejected a submission because its fee was too low.
+pub fn is_underpriced(error: &Error) -> bool {
+    let message = error.to_string().to_ascii_lowercase();
+    ["insufficient fee", "insufficient minimum gas price", "gas price too low", "fee too low"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// Attempts of one submission under a [`GasConfig`].
+#[derive(Debug, Clone)]
+pub struct GasSchedule<'a> {
+    config: &'a GasConfig,
+    /// Estimated gas including the configured adjustment.
+    pub gas: u64,
+    pub price: f64,
+    pub attempt: u32,
+}
+
+impl<'a> GasSchedule<'a> {
+    pub fn new(config: &'a GasConfig, blobs: &[Blob]) -> Self {
+        Self {
+            config,
+            gas: (estimate_gas(blobs) as f64 * config.gas_adjustment).ceil() as u64,
+            price: config.price.min(config.max_price),
+            attempt: 1,
+        }
+    }
+
+    pub fn tx_config(&self) -> TxConfig {
+        TxConfig {
+            gas: Some(self.gas),
+            gas_price: Some(self.price),
+            ..TxConfig::default()
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.config.timeout_secs)
+    }
+
+    /// Moves to the next attempt with an escalated price.
+    ///
+    /// Returns `false` once attempts are exhausted or the price is already at the cap.
+    pub fn escalate(&mut self) -> bool {
+        if self.attempt >= self.config.max_attempts || self.price >= self.config.max_price {
+            return false;
+        }
+        self.attempt += 1;
+        self.price = (self.price * self.config.multiplier).min(self.config.max_price);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::{consts::appconsts::AppVersion, nmt::Namespace};
+
+    fn blob(len: usize) -> Blob {
+        Blob::new(Namespace::new_v0(b"rollup").unwrap(), vec![1; len], AppVersion::V2).unwrap()
+    }
+
+    #[test]
+    fn test_estimate_gas() {
+        // one share: 512 * 8 + 10 * 70 + 75000
+        assert_eq!(estimate_gas(&[blob(100)]), 79_796);
+        // three shares in one blob plus a second single share blob
+        assert_eq!(estimate_gas(&[blob(1000), blob(10)]), 4 * 4096 + 1400 + 75_000);
+        assert_eq!(fee(80_000, 0.002), 160);
+        assert_eq!(fee(80_001, 0.002), 161);
+    }
+
+    #[test]
+    fn test_escalation_is_capped() {
+        let config = GasConfig {
+            price: 0.002,
+            multiplier: 2.0,
+            max_price: 0.005,
+            max_attempts: 10,
+            gas_adjustment: 1.5,
+            ..GasConfig::default()
+        };
+        let mut schedule = GasSchedule::new(&config, &[blob(100)]);
+        assert_eq!(schedule.gas, (79_796.0f64 * 1.5).ceil() as u64);
+        assert_eq!(schedule.tx_config().gas_price, Some(0.002));
+
+        let mut prices = vec![schedule.price];
+        while schedule.escalate() {
+            prices.push(schedule.price);
+        }
+        assert_eq!(prices, vec![0.002, 0.004, 0.005]);
+
+        let config = GasConfig { max_attempts: 2, ..config };
+        let mut schedule = GasSchedule::new(&config, &[blob(100)]);
+        assert!(schedule.escalate());
+        assert!(!schedule.escalate());
+        assert_eq!(schedule.attempt, 2);
+    }
+
+    #[test]
+    fn test_underpriced_errors() {
+        let err = Error::from(jsonrpsee::core::ClientError::Custom(
+            "insufficient minimum gas price for this node; got: 0.001 required: 0.002: insufficient fee".into(),
+        ));
+        assert!(is_underpriced(&err));
+        assert!(!is_underpriced(&Error::Proof("insufficient data".into())));
+    }
+}
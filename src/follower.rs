@@ -0,0 +1,437 @@
+// src/follower.rs
+//! Derivation: reading rollup blocks back from the namespace.
+//!
+//! [`CelestiaClient::follow`] walks Celestia heights from a starting point,
+//! waits for new headers once it reaches the tip, and turns every blob in the
+//! namespace into a [`RollupBlock`]. Blocks come out by height, then by
+//! position in the data square, so every reader derives the same sequence.
+//!
+//! Chunk blobs are passed over; their content is read through the manifest
+//! that follows them. Anyone can post to a namespace, so blobs that do not
+//! decode into a block, including envelopes under keys this reader does not
+//! hold and manifests whose chunks cannot be found, are logged and skipped.
+//! Only failing to reach the node or the key provider stops the stream, so
+//! no block is lost to an outage.
+
+use futures::stream::{BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::block::RollupBlock;
+use crate::chunking;
+use crate::config::FollowerConfig;
+use crate::encryption::EncryptionError;
+use crate::error::{Error, Result};
+use crate::poster::{CelestiaClient, SequenceSpan};
+use crate::span;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CheckpointError {
+    #[error("follower checkpoint {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("follower checkpoint {path} is corrupt: {source}")]
+    Corrupt {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+/// A block read from the namespace, with the span of the blob carrying it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowedBlock {
+    pub span: SequenceSpan,
+    pub block: RollupBlock,
+}
+
+/// Position of the follower in the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Height being read; everything below it has been handed out.
+    pub height: u64,
+    /// Blobs at `height`, in square order, that were already handled.
+    pub blobs: usize,
+}
+
+impl Checkpoint {
+    pub fn at(height: u64) -> Self {
+        Self { height, blobs: 0 }
+    }
+
+    /// Reads the checkpoint at `path`, `None` if the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, CheckpointError> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => return Err(CheckpointError::Io { path: path.to_path_buf(), source }),
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|source| CheckpointError::Corrupt { path: path.to_path_buf(), source })
+    }
+
+    /// Replaces the checkpoint at `path` atomically.
+    pub fn store(&self, path: &Path) -> Result<(), CheckpointError> {
+        let io = |source| CheckpointError::Io { path: path.to_path_buf(), source };
+        let tmp = path.with_extension("tmp");
+        let data = serde_json::to_vec(self).expect("checkpoint serializes to JSON");
+        std::fs::write(&tmp, data).map_err(io)?;
+        std::fs::rename(&tmp, path).map_err(io)
+    }
+}
+
+impl CelestiaClient {
+    /// Streams the rollup blocks posted to the client's namespace.
+    ///
+    /// Reading starts at the checkpoint in `config.checkpoint_path` if there
+    /// is one, and at `config.start_height` otherwise. The checkpoint moves
+    /// past a block when the next one is requested, so after a crash the last
+    /// block received may be delivered again, but none is skipped. The stream
+    /// ends after its first error; following again resumes at the checkpoint.
+    pub fn follow(
+        &self,
+        config: &FollowerConfig,
+    ) -> Result<impl Stream<Item = Result<FollowedBlock>> + '_> {
+        let checkpoint = match &config.checkpoint_path {
+            Some(path) => Checkpoint::load(path)?,
+            None => None,
+        };
+        let cursor = checkpoint.unwrap_or(Checkpoint::at(config.start_height));
+        tracing::info!("Following namespace from height {} (blob {})", cursor.height, cursor.blobs);
+
+        let follower = Follower {
+            client: self,
+            checkpoint_path: config.checkpoint_path.clone(),
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+            cursor,
+            pending: VecDeque::new(),
+            handed_out: None,
+            head: 0,
+            heads: None,
+            subscribed: false,
+        };
+        Ok(futures::stream::try_unfold(follower, |mut follower| async move {
+            let block = follower.next_block().await?;
+            Ok(Some((block, follower)))
+        }))
+    }
+}
+
+struct Follower<'a> {
+    client: &'a CelestiaClient,
+    checkpoint_path: Option<PathBuf>,
+    poll_interval: Duration,
+    /// Next blob to read.
+    cursor: Checkpoint,
+    /// Decoded blocks not handed out yet, each with the checkpoint past it.
+    pending: VecDeque<(FollowedBlock, Checkpoint)>,
+    /// Checkpoint to store once the consumer asks for the next block.
+    handed_out: Option<Checkpoint>,
+    /// Highest header height seen so far.
+    head: u64,
+    heads: Option<BoxStream<'static, u64>>,
+    subscribed: bool,
+}
+
+impl Follower<'_> {
+    async fn next_block(&mut self) -> Result<FollowedBlock> {
+        loop {
+            if let Some(checkpoint) = self.handed_out.take() {
+                if let Some(path) = &self.checkpoint_path {
+                    checkpoint.store(path)?;
+                }
+            }
+            if let Some((block, after)) = self.pending.pop_front() {
+                self.handed_out = Some(after);
+                return Ok(block);
+            }
+            self.read_height().await?;
+        }
+    }
+
+    /// Decodes the remaining blobs at the cursor's height and moves it to the next height.
+    async fn read_height(&mut self) -> Result<()> {
+        let height = self.cursor.height;
+        self.wait_for(height).await?;
+
+        let backend = self.client.backend();
+        let dah = backend.header(height).await?;
+        let mut blobs = backend.blobs(height, &[self.client.namespace()]).await?;
+        blobs.sort_by_key(|blob| blob.index);
+
+        for (i, blob) in blobs.into_iter().enumerate().skip(self.cursor.blobs) {
+            let span = span::blob_span(height, &blob, dah.square_width())?;
+            if chunking::is_chunk(&blob.data) {
+                tracing::debug!("Passing over chunk blob {}", span);
+                continue;
+            }
+            match self.client.decode_block(blob.data).await {
+                Ok(block) => {
+                    let after = Checkpoint { height, blobs: i + 1 };
+                    self.pending.push_back((FollowedBlock { span, block }, after));
+                }
+                Err(e) if is_transient(&e) => return Err(e),
+                Err(e) => tracing::warn!("Skipping blob {} that is not a rollup block: {}", span, e),
+            }
+        }
+
+        let next = Checkpoint::at(height + 1);
+        match self.pending.back_mut() {
+            Some((_, after)) => *after = next,
+            None => self.handed_out = Some(next),
+        }
+        self.cursor = next;
+        Ok(())
+    }
+
+    async fn wait_for(&mut self, height: u64) -> Result<()> {
+        if self.head >= height {
+            return Ok(());
+        }
+        self.head = self.client.backend().head().await?;
+        while self.head < height {
+            self.head = self.head.max(self.next_head().await?);
+        }
+        Ok(())
+    }
+
+    /// Height of the next header, pushed by the node if it supports
+    /// subscriptions and polled otherwise.
+    async fn next_head(&mut self) -> Result<u64> {
+        if !self.subscribed {
+            self.subscribed = true;
+            self.heads = self.client.backend().subscribe_heads().await?;
+            if self.heads.is_none() {
+                tracing::info!("Polling for new headers every {:?}", self.poll_interval);
+            }
+        }
+
+        if let Some(heads) = &mut self.heads {
+            match heads.next().await {
+                Some(height) => return Ok(height),
+                None => {
+                    tracing::warn!("Header subscription closed, falling back to polling");
+                    self.heads = None;
+                }
+            }
+        }
+
+        tokio::time::sleep(self.poll_interval).await;
+        self.client.backend().head().await
+    }
+}
+
+/// Whether a decoding failure comes from an outage, after which the same
+/// blob may decode, rather than from the blob itself.
+fn is_transient(e: &Error) -> bool {
+    match e {
+        // chunks above the height being read were never posted before their manifest
+        Error::HeightUnavailable { .. } => false,
+        Error::Encryption(EncryptionError::Provider(_)) => true,
+        _ => e.is_retryable(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{DaBackend, MemoryBackend};
+    use crate::block::BlockHeader;
+    use crate::config::{CelestiaConfig, ConfirmationConfig};
+    use crate::chunking::ChunkManifest;
+    use crate::encryption::{self, Cipher, DataKey, KeyRing};
+    use celestia_types::nmt::Namespace;
+    use celestia_types::row_namespace_data::NamespaceData;
+    use celestia_types::sample::Sample;
+    use celestia_types::{consts::appconsts::AppVersion, Blob, Commitment, DataAvailabilityHeader, ShareProof, TxConfig};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn block(number: u64) -> RollupBlock {
+        RollupBlock {
+            header: BlockHeader { number, ..BlockHeader::default() },
+            transactions: vec![vec![number as u8; 3]],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_follow_resumes_from_checkpoint() {
+        let config = CelestiaConfig {
+            confirmation: ConfirmationConfig { depth: 0, ..ConfirmationConfig::default() },
+            ..CelestiaConfig::default()
+        };
+        let backend = Arc::new(MemoryBackend::new());
+        let client = CelestiaClient::with_backend(&config, Box::new(backend.clone())).unwrap();
+
+        client.submit_blocks(&[block(1), block(2)]).await.unwrap();
+        // garbage in the namespace is skipped
+        let junk = Blob::new(config.namespace, b"not a block".to_vec(), AppVersion::V2).unwrap();
+        backend.submit(&[junk], TxConfig::default()).await.unwrap();
+        backend.produce_empty_blocks(1).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let follower = FollowerConfig {
+            checkpoint_path: Some(dir.path().join("follower.json")),
+            poll_interval_ms: 10,
+            ..FollowerConfig::default()
+        };
+        let numbers = |blocks: &[FollowedBlock]| blocks.iter().map(|b| b.block.header.number).collect::<Vec<_>>();
+
+        let mut stream = Box::pin(client.follow(&follower).unwrap());
+        let first = [stream.next().await.unwrap().unwrap(), stream.next().await.unwrap().unwrap()];
+        assert_eq!(numbers(&first), [1, 2]);
+        assert_eq!(first[0].span.height, 1);
+
+        // the tip is reached; block 3 arrives through the header subscription
+        let (third, _) = tokio::join!(stream.next(), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.submit_block(&block(3)).await.unwrap()
+        });
+        let third = third.unwrap().unwrap();
+        assert_eq!((third.block.header.number, third.span.height), (3, 4));
+        drop(stream);
+
+        // block 3 was not acknowledged by asking for the next one
+        let mut stream = Box::pin(client.follow(&follower).unwrap());
+        assert_eq!(stream.next().await.unwrap().unwrap(), third);
+        let checkpoint = Checkpoint::load(follower.checkpoint_path.as_deref().unwrap()).unwrap();
+        assert_eq!(checkpoint, Some(Checkpoint::at(4)));
+    }
+
+    /// Bytes that neither compress nor fit in one 1000 byte blob.
+    fn noise(len: usize) -> Vec<u8> {
+        (0..len as u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect()
+    }
+
+    fn chunked_config() -> CelestiaConfig {
+        CelestiaConfig {
+            confirmation: ConfirmationConfig { depth: 0, ..ConfirmationConfig::default() },
+            max_blob_size: 1000,
+            max_pfb_size: 2000,
+            ..CelestiaConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_follow_encrypted_chunked_blocks() {
+        let config = chunked_config();
+        let backend = Arc::new(MemoryBackend::new());
+        let keys = HashMap::from([("k1".to_string(), DataKey::new([1; encryption::KEY_LEN]))]);
+        let client = CelestiaClient::with_backend(&config, Box::new(backend.clone()))
+            .unwrap()
+            .with_key_provider(Cipher::default(), Box::new(KeyRing::new("k1", keys).unwrap()));
+
+        let big = RollupBlock {
+            header: BlockHeader { number: 1, ..BlockHeader::default() },
+            transactions: vec![noise(3000)],
+        };
+        let manifest_span = client.submit_block(&big).await.unwrap();
+
+        // blobs anyone could post: envelopes under a key we do not hold or
+        // sealed with another key, manifests of chunks that are not there
+        let missing = |height| SequenceSpan {
+            height,
+            start_index: 0,
+            data_len: 1,
+            commitment: Some(Blob::new(config.namespace, b"never posted".to_vec(), AppVersion::V2).unwrap().commitment),
+        };
+        let forged = [
+            encryption::seal(Cipher::default(), "k2", &DataKey::new([2; encryption::KEY_LEN]), b"block").unwrap(),
+            encryption::seal(Cipher::default(), "k1", &DataKey::new([2; encryption::KEY_LEN]), b"block").unwrap(),
+            ChunkManifest::new(b"block", vec![missing(1)]).encode(),
+            ChunkManifest::new(b"block", vec![missing(1_000_000)]).encode(),
+        ]
+        .map(|data| Blob::new(config.namespace, data, AppVersion::V2).unwrap());
+        backend.submit(&forged, TxConfig::default()).await.unwrap();
+        client.submit_block(&block(2)).await.unwrap();
+
+        let mut stream = Box::pin(client.follow(&FollowerConfig::default()).unwrap());
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!((first.block, first.span), (big, manifest_span));
+        assert_eq!(stream.next().await.unwrap().unwrap().block, block(2));
+    }
+
+    /// Fails the next blob lookup once armed, as if the node had timed out.
+    struct Flaky {
+        inner: Arc<MemoryBackend>,
+        fail_next: AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl DaBackend for Flaky {
+        async fn submit(&self, blobs: &[Blob], config: TxConfig) -> Result<u64> {
+            self.inner.submit(blobs, config).await
+        }
+        async fn head(&self) -> Result<u64> {
+            self.inner.head().await
+        }
+        async fn header(&self, height: u64) -> Result<DataAvailabilityHeader> {
+            self.inner.header(height).await
+        }
+        async fn blobs(&self, height: u64, namespaces: &[Namespace]) -> Result<Vec<Blob>> {
+            self.inner.blobs(height, namespaces).await
+        }
+        async fn blob(&self, height: u64, namespace: Namespace, commitment: Commitment) -> Result<Blob> {
+            if self.fail_next.swap(false, Ordering::SeqCst) {
+                return Err(jsonrpsee::core::ClientError::RequestTimeout.into());
+            }
+            self.inner.blob(height, namespace, commitment).await
+        }
+        async fn namespace_data(&self, height: u64, namespace: Namespace) -> Result<NamespaceData> {
+            self.inner.namespace_data(height, namespace).await
+        }
+        async fn sample(&self, height: u64, row: u16, column: u16) -> Result<Sample> {
+            self.inner.sample(height, row, column).await
+        }
+        async fn share_proof(&self, height: u64, start: u64, end: u64) -> Result<ShareProof> {
+            self.inner.share_proof(height, start, end).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_chunks_stop_the_stream() {
+        let config = chunked_config();
+        let flaky = Arc::new(Flaky { inner: Arc::new(MemoryBackend::new()), fail_next: AtomicBool::new(false) });
+        let client = CelestiaClient::with_backend(&config, Box::new(flaky.clone())).unwrap();
+        let big = RollupBlock {
+            header: BlockHeader { number: 1, ..BlockHeader::default() },
+            transactions: vec![noise(3000)],
+        };
+        client.submit_block(&big).await.unwrap();
+        flaky.fail_next.store(true, Ordering::SeqCst);
+
+        let dir = tempfile::tempdir().unwrap();
+        let follower = FollowerConfig {
+            checkpoint_path: Some(dir.path().join("follower.json")),
+            ..FollowerConfig::default()
+        };
+        let mut stream = Box::pin(client.follow(&follower).unwrap());
+        assert!(stream.next().await.unwrap().unwrap_err().is_retryable());
+        assert!(stream.next().await.is_none());
+        drop(stream);
+
+        // the block is read from where it stopped once the node answers
+        let mut stream = Box::pin(client.follow(&follower).unwrap());
+        assert_eq!(stream.next().await.unwrap().unwrap().block, big);
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("follower.json");
+        assert_eq!(Checkpoint::load(&path).unwrap(), None);
+
+        Checkpoint { height: 12, blobs: 3 }.store(&path).unwrap();
+        Checkpoint::at(13).store(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), Some(Checkpoint { height: 13, blobs: 0 }));
+
+        std::fs::write(&path, b"{\"height\":").unwrap();
+        assert!(matches!(Checkpoint::load(&path), Err(CheckpointError::Corrupt { .. })));
+    }
+}
@@ -0,0 +1,75 @@
+//! Rollup data availability on Celestia with settlement on an Ethereum L1.
+//!
+//! Components are gated by cargo features so downstream crates only pull in
+//! what they use:
+//!
+//! - `celestia`: node access through a [`backend`], blob posting ([`poster`])
+//!   with payload [`compression`], [`encryption`], [`chunking`] and
+//!   [`confirmation`] tracking with optional availability [`sampling`],
+//!   reading blocks back ([`follower`]), and proof generation
+//!   ([`celestia_prover`]).
+//! - `l1-settlement`: settlement contract bindings ([`settlement_verification`]),
+//!   the [`blobstream`] commitment index and transaction [`signer`]s.
+//! - `cli`: the `celestia-integration` command line binary.
+//!
+//! Both features share the [`proof`] types and RFC 6962 [`merkle`] proofs,
+//! which need neither client stack. A sequencer that only posts data can
+//! depend on this crate with `default-features = false, features =
+//! ["celestia"]` and no alloy in its tree, and a settlement service with
+//! `features = ["l1-settlement"]` settles `prove` bundles without the
+//! Celestia client.
+
+pub mod block;
+#[cfg(feature = "l1-settlement")]
+pub mod blobstream;
+pub mod config;
+pub mod error;
+#[cfg(feature = "celestia")]
+pub mod backend;
+#[cfg(feature = "celestia")]
+pub mod celestia_prover;
+#[cfg(feature = "celestia")]
+pub mod chunking;
+#[cfg(feature = "celestia")]
+pub mod compression;
+#[cfg(feature = "celestia")]
+pub mod confirmation;
+#[cfg(feature = "celestia")]
+pub mod dedup;
+#[cfg(feature = "celestia")]
+pub mod encryption;
+#[cfg(feature = "celestia")]
+pub mod fees;
+#[cfg(feature = "celestia")]
+pub mod follower;
+#[cfg(all(test, feature = "celestia"))]
+mod mock_node;
+#[cfg(any(feature = "celestia", feature = "l1-settlement"))]
+pub mod merkle;
+#[cfg(feature = "celestia")]
+pub mod poster;
+#[cfg(any(feature = "celestia", feature = "l1-settlement"))]
+pub mod proof;
+#[cfg(feature = "celestia")]
+pub mod queue;
+#[cfg(feature = "celestia")]
+pub mod sampling;
+#[cfg(feature = "celestia")]
+pub mod span;
+#[cfg(feature = "l1-settlement")]
+pub mod settlement_verification;
+#[cfg(feature = "l1-settlement")]
+pub mod signer;
+
+pub use block::RollupBlock;
+pub use config::{Config, ConfigError};
+pub use error::{Error, Result};
+
+#[cfg(feature = "celestia")]
+pub use backend::{CelestiaBackend, DaBackend, MemoryBackend};
+#[cfg(feature = "celestia")]
+pub use celestia_prover::CelestiaProver;
+#[cfg(feature = "celestia")]
+pub use poster::{BlobPayload, CelestiaClient, SequenceSpan};
+#[cfg(feature = "l1-settlement")]
+pub use signer::L1Signer;
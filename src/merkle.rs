@@ -0,0 +1,198 @@
+// src/merkle.rs
+//! RFC 6962 binary Merkle trees, as Celestia and Blobstream use them.
+//!
+//! A block's data root is the root of the tree whose leaves are its row roots
+//! followed by its column roots, each encoded as a 90 byte namespaced hash.
+//! Blobstream's verifier takes proofs in that tree as side nodes, the leaf
+//! index (`key`) and the number of leaves, with the side node nearest the
+//! leaf first; [`BinaryMerkleProof`] has the same shape.
+
+#[cfg(feature = "celestia")]
+use celestia_types::{nmt::NamespacedHashExt, DataAvailabilityHeader};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// RFC 6962 Merkle proof of the leaf at `key` in a tree of `num_leaves`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryMerkleProof {
+    pub side_nodes: Vec<[u8; 32]>,
+    pub key: u64,
+    pub num_leaves: u64,
+}
+
+impl BinaryMerkleProof {
+    /// Root of the tree the proof describes, with `leaf` at `key`.
+    pub fn compute_root(&self, leaf: &[u8]) -> Result<[u8; 32]> {
+        if self.key >= self.num_leaves {
+            return Err(Error::Proof(format!("leaf {} is outside a tree of {} leaves", self.key, self.num_leaves)));
+        }
+        compute_root(self.key, self.num_leaves, leaf_hash(leaf), &self.side_nodes)
+    }
+
+    pub fn verify(&self, leaf: &[u8], root: &[u8; 32]) -> Result<()> {
+        if self.compute_root(leaf)? != *root {
+            return Err(Error::Proof(format!("leaf {} does not lead to root {}", self.key, hex::encode(root))));
+        }
+        Ok(())
+    }
+}
+
+fn compute_root(key: u64, num_leaves: u64, hash: [u8; 32], side_nodes: &[[u8; 32]]) -> Result<[u8; 32]> {
+    if num_leaves == 1 {
+        return match side_nodes {
+            [] => Ok(hash),
+            _ => Err(Error::Proof(format!("{} side nodes left over", side_nodes.len()))),
+        };
+    }
+    let Some((sibling, below)) = side_nodes.split_last() else {
+        return Err(Error::Proof(format!("missing side nodes for a subtree of {num_leaves} leaves")));
+    };
+    let split = split_point(num_leaves);
+    if key < split {
+        Ok(node_hash(&compute_root(key, split, hash, below)?, sibling))
+    } else {
+        Ok(node_hash(sibling, &compute_root(key - split, num_leaves - split, hash, below)?))
+    }
+}
+
+pub fn leaf_hash(leaf: &[u8]) -> [u8; 32] {
+    Sha256::new().chain_update([LEAF_PREFIX]).chain_update(leaf).finalize().into()
+}
+
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new().chain_update([NODE_PREFIX]).chain_update(left).chain_update(right).finalize().into()
+}
+
+/// Leaves in the left subtree of a tree of `num_leaves > 1`: the largest
+/// power of two below it.
+fn split_point(num_leaves: u64) -> u64 {
+    1 << (63 - (num_leaves - 1).leading_zeros())
+}
+
+/// Root of the tree over `leaves`; the hash of nothing when there are none.
+pub fn root<T: AsRef<[u8]>>(leaves: &[T]) -> [u8; 32] {
+    match leaves {
+        [] => Sha256::digest([]).into(),
+        [leaf] => leaf_hash(leaf.as_ref()),
+        _ => {
+            let (left, right) = leaves.split_at(split_point(leaves.len() as u64) as usize);
+            node_hash(&root(left), &root(right))
+        }
+    }
+}
+
+/// Proof of `leaves[index]`.
+pub fn prove<T: AsRef<[u8]>>(leaves: &[T], index: usize) -> Result<BinaryMerkleProof> {
+    if index >= leaves.len() {
+        return Err(Error::Proof(format!("leaf {index} is outside a tree of {} leaves", leaves.len())));
+    }
+    let mut side_nodes = Vec::new();
+    collect_side_nodes(leaves, index, &mut side_nodes);
+    Ok(BinaryMerkleProof { side_nodes, key: index as u64, num_leaves: leaves.len() as u64 })
+}
+
+fn collect_side_nodes<T: AsRef<[u8]>>(leaves: &[T], index: usize, side_nodes: &mut Vec<[u8; 32]>) {
+    if leaves.len() <= 1 {
+        return;
+    }
+    let (left, right) = leaves.split_at(split_point(leaves.len() as u64) as usize);
+    if index < left.len() {
+        collect_side_nodes(left, index, side_nodes);
+        side_nodes.push(root(right));
+    } else {
+        collect_side_nodes(right, index - left.len(), side_nodes);
+        side_nodes.push(root(left));
+    }
+}
+
+/// Leaves of the data root tree: the row roots, then the column roots.
+#[cfg(feature = "celestia")]
+fn data_root_leaves(dah: &DataAvailabilityHeader) -> Vec<[u8; 90]> {
+    dah.row_roots().iter().chain(dah.column_roots()).map(|root| root.to_array()).collect()
+}
+
+/// Data root of `dah`, the same as `dah.hash()`.
+#[cfg(feature = "celestia")]
+pub fn data_root(dah: &DataAvailabilityHeader) -> [u8; 32] {
+    root(&data_root_leaves(dah))
+}
+
+/// Proof of the root of `row` against the data root of `dah`.
+#[cfg(feature = "celestia")]
+pub fn row_root_proof(dah: &DataAvailabilityHeader, row: u16) -> Result<BinaryMerkleProof> {
+    if row >= dah.square_width() {
+        return Err(Error::Proof(format!("row {row} is outside a square {} wide", dah.square_width())));
+    }
+    prove(&data_root_leaves(dah), usize::from(row))
+}
+
+/// Proofs of every row root against the data root of `dah`, in row order.
+#[cfg(feature = "celestia")]
+pub fn row_root_proofs(dah: &DataAvailabilityHeader) -> Vec<BinaryMerkleProof> {
+    let leaves = data_root_leaves(dah);
+    (0..dah.row_roots().len()).map(|row| prove(&leaves, row).expect("rows are leaves")).collect()
+}
+
+#[cfg(all(test, feature = "celestia"))]
+mod tests {
+    use super::*;
+    use crate::backend::{DaBackend, MemoryBackend};
+    use celestia_proto::celestia::core::v1::proof::RowProof as RawRowProof;
+    use celestia_types::{consts::appconsts::AppVersion, hash::Hash, nmt::Namespace, Blob, MerkleProof, TxConfig};
+    use proptest::prelude::*;
+
+    #[tokio::test]
+    async fn test_row_root_proofs() {
+        let backend = MemoryBackend::new();
+        let blobs = [
+            Blob::new(Namespace::new_v0(b"rollup").unwrap(), vec![1; 3000], AppVersion::V2).unwrap(),
+            Blob::new(Namespace::new_v0(b"filler").unwrap(), vec![2; 100], AppVersion::V2).unwrap(),
+        ];
+        let height = backend.submit(&blobs, TxConfig::default()).await.unwrap();
+        let dah = backend.header(height).await.unwrap();
+        let Hash::Sha256(expected) = dah.hash() else {
+            panic!("data root is sha256");
+        };
+        assert_eq!(data_root(&dah), expected);
+
+        let proofs = row_root_proofs(&dah);
+        assert_eq!(proofs.len(), usize::from(dah.square_width()));
+        for (row, proof) in proofs.iter().enumerate() {
+            let row_root = dah.row_root(row as u16).unwrap().to_array();
+            proof.verify(&row_root, &expected).unwrap();
+            assert_eq!(proof, &row_root_proof(&dah, row as u16).unwrap());
+
+            // the same proof celestia-types builds for the row
+            let celestia = RawRowProof::from(dah.row_proof(row as u16..=row as u16).unwrap());
+            let aunts: Vec<Vec<u8>> = proof.side_nodes.iter().map(|node| node.to_vec()).collect();
+            assert_eq!(celestia.proofs[0].aunts, aunts);
+        }
+
+        // a proof only holds for its own row root and position
+        let other_root = dah.row_root(1).unwrap().to_array();
+        assert!(proofs[0].verify(&other_root, &expected).is_err());
+        let moved = BinaryMerkleProof { key: 1, ..proofs[0].clone() };
+        assert!(moved.verify(&dah.row_root(0).unwrap().to_array(), &expected).is_err());
+        assert!(row_root_proof(&dah, dah.square_width()).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn fuzz_proofs_lead_to_the_root(leaves in prop::collection::vec(any::<[u8; 8]>(), 1..40), index in any::<prop::sample::Index>()) {
+            let index = index.index(leaves.len());
+            let (_, expected) = MerkleProof::new(index, &leaves).unwrap();
+            prop_assert_eq!(root(&leaves), expected);
+
+            let proof = prove(&leaves, index).unwrap();
+            prop_assert_eq!(proof.compute_root(&leaves[index]).unwrap(), expected);
+            let mut altered = leaves[index];
+            altered[0] ^= 1;
+            prop_assert!(proof.verify(&altered, &expected).is_err());
+        }
+    }
+}
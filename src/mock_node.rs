@@ -0,0 +1,181 @@
+// src/mock_node.rs
+//! In-process stand-in for the celestia-node JSON-RPC API, for tests.
+//!
+//! Serves the methods [`crate::backend::CelestiaBackend`] calls over plain
+//! HTTP on a random local port, so tests go through the real `celestia_rpc`
+//! client and its (de)serialization. Chain state lives in a
+//! [`MemoryBackend`], which builds real shares, row roots and NMT proofs.
+//! Headers are generated around each block's DAH with celestia-types' test
+//! utilities, so they pass the client's validation.
+
+use celestia_rpc::share::GetRangeResponse;
+use celestia_types::{
+    nmt::Namespace, test_utils::ExtendedHeaderGenerator, Blob, Commitment, ExtendedHeader, Share, TxConfig,
+};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::backend::{DaBackend, MemoryBackend};
+
+/// JSON-RPC error code celestia-node uses for failed calls.
+const CALL_ERROR: i64 = 1;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+pub struct MockNode {
+    url: String,
+    backend: Arc<MemoryBackend>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockNode {
+    /// Starts serving a chain holding one empty block, so there is always a
+    /// head; the first submission lands at height 2.
+    pub async fn start() -> Self {
+        let backend = Arc::new(MemoryBackend::new());
+        backend.produce_empty_blocks(1).expect("empty block");
+        let state = Arc::new(State {
+            backend: backend.clone(),
+            headers: Mutex::new((ExtendedHeaderGenerator::new(), Vec::new())),
+        });
+
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request))) }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = stopped.await;
+        }));
+        Self { url, backend, shutdown: Some(shutdown) }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Chain behind the node, e.g. to produce blocks or inject failures.
+    pub fn backend(&self) -> &MemoryBackend {
+        &self.backend
+    }
+}
+
+impl Drop for MockNode {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+struct State {
+    backend: Arc<MemoryBackend>,
+    /// Header generator and the headers handed out so far, by height - 1.
+    headers: Mutex<(ExtendedHeaderGenerator, Vec<ExtendedHeader>)>,
+}
+
+type CallResult = Result<Value, (i64, String)>;
+
+async fn handle(state: Arc<State>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(call) => {
+            let method = call["method"].as_str().unwrap_or_default();
+            let result = state.call(method, call["params"].clone()).await;
+            respond(call["id"].clone(), result)
+        }
+        Err(e) => respond(Value::Null, Err((-32700, e.to_string()))),
+    };
+
+    Ok(Response::builder()
+        .header("content-type", "application/json")
+        .body(Body::from(response.to_string()))
+        .expect("valid response"))
+}
+
+fn respond(id: Value, result: CallResult) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => {
+            json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+        }
+    }
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, (i64, String)> {
+    serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, e.to_string()))
+}
+
+fn to_json<T: serde::Serialize>(result: crate::Result<T>) -> CallResult {
+    let value = result.map_err(|e| (CALL_ERROR, e.to_string()))?;
+    Ok(serde_json::to_value(value).expect("response serializes"))
+}
+
+impl State {
+    async fn call(&self, method: &str, params_value: Value) -> CallResult {
+        match method {
+            "blob.Submit" => {
+                let (blobs, options): (Vec<Blob>, Value) = params(params_value)?;
+                let config = TxConfig {
+                    gas_price: options.get("gas_price").and_then(Value::as_f64),
+                    gas: options.get("gas").and_then(Value::as_u64),
+                    ..TxConfig::default()
+                };
+                to_json(self.backend.submit(&blobs, config).await)
+            }
+            "blob.Get" => {
+                let (height, namespace, commitment): (u64, Namespace, Commitment) = params(params_value)?;
+                to_json(self.backend.blob(height, namespace, commitment).await)
+            }
+            "blob.GetAll" => {
+                let (height, namespaces): (u64, Vec<Namespace>) = params(params_value)?;
+                // like celestia-node, an empty result is null
+                let blobs = self.backend.blobs(height, &namespaces).await;
+                to_json(blobs.map(|blobs| (!blobs.is_empty()).then_some(blobs)))
+            }
+            "header.GetByHeight" => {
+                let (height,): (u64,) = params(params_value)?;
+                to_json(self.header(height).await)
+            }
+            "header.LocalHead" => match self.backend.head().await {
+                Ok(head) => to_json(self.header(head).await),
+                Err(e) => to_json::<()>(Err(e)),
+            },
+            "share.GetNamespaceData" => {
+                let (height, namespace): (u64, Namespace) = params(params_value)?;
+                to_json(self.backend.namespace_data(height, namespace).await)
+            }
+            "share.GetRange" => {
+                let (height, start, end): (u64, u64, u64) = params(params_value)?;
+                let range = self.backend.share_proof(height, start, end).await.and_then(|proof| {
+                    let shares = proof.shares().iter().map(|share| Share::from_raw(share)).collect::<Result<_, _>>()?;
+                    Ok(GetRangeResponse { shares, proof })
+                });
+                to_json(range)
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("method {method} not found"))),
+        }
+    }
+
+    /// Header at `height`, generating the headers up to it on first use.
+    async fn header(&self, height: u64) -> crate::Result<ExtendedHeader> {
+        // fails for heights the chain does not have
+        self.backend.header(height).await?;
+
+        let mut headers = self.headers.lock().await;
+        let (generator, generated) = &mut *headers;
+        while (generated.len() as u64) < height {
+            let dah = self.backend.header(generated.len() as u64 + 1).await?;
+            generated.push(generator.next_with_dah(dah));
+        }
+        Ok(generated[height as usize - 1].clone())
+    }
+}
@@ -0,0 +1,93 @@
+// src/proof.rs
+//! Proofs of rollup data, in the shape blobstream-contracts takes them.
+//!
+//! The prover builds these from a Celestia node and settlement encodes them
+//! into contract calls. They only hold plain bytes and integers, so a
+//! settlement service can read a bundle written by `prove` without the
+//! Celestia client stack.
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::merkle::BinaryMerkleProof;
+
+/// Size of an encoded namespace: the version byte then the 28 byte id.
+pub const NS_SIZE: usize = 29;
+
+/// Node of a namespaced Merkle tree, namespaces encoded as version byte then id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceNode {
+    pub min: [u8; NS_SIZE],
+    pub max: [u8; NS_SIZE],
+    pub digest: [u8; 32],
+}
+
+/// Proof of the leaves `begin_key..end_key` of a row against its root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceMerkleMultiproof {
+    pub begin_key: u64,
+    pub end_key: u64,
+    pub side_nodes: Vec<NamespaceNode>,
+}
+
+/// Shares of one namespace proven against a block's data root, as
+/// blobstream-contracts' `DAVerifier` takes them: one multiproof per row the
+/// shares occupy, then those rows' roots and their proofs to the data root.
+/// The attestation of the data root is added at settlement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharesProof {
+    pub data: Vec<Vec<u8>>,
+    pub share_proofs: Vec<NamespaceMerkleMultiproof>,
+    pub namespace: [u8; NS_SIZE],
+    pub row_roots: Vec<NamespaceNode>,
+    pub row_proofs: Vec<BinaryMerkleProof>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataRootTuple {
+    pub height: u64,
+    pub data_root: [u8; 32],
+}
+
+impl DataRootTuple {
+    /// ABI encoding of the tuple, the leaf Blobstream commits to.
+    pub fn encode(&self) -> [u8; 64] {
+        let mut leaf = [0; 64];
+        leaf[24..32].copy_from_slice(&self.height.to_be_bytes());
+        leaf[32..].copy_from_slice(&self.data_root);
+        leaf
+    }
+}
+
+/// Blobstream data commitment: the root of the data root tuples of Celestia
+/// heights `start..end`, attested on L1 under `nonce`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataCommitment {
+    pub nonce: u64,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl DataCommitment {
+    pub fn contains(&self, height: u64) -> bool {
+        (self.start..self.end).contains(&height)
+    }
+}
+
+/// Proof that `tuple` is a leaf of a data commitment, as the contract's
+/// `verifyAttestation` takes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationProof {
+    pub nonce: u64,
+    pub tuple: DataRootTuple,
+    pub proof: BinaryMerkleProof,
+    /// Root the proof leads to, to compare with the attested one.
+    pub data_commitment: [u8; 32],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerificationData {
+    pub shares_proof: SharesProof,
+    pub data_root_tuple: DataRootTuple,
+    pub start_index: u64,
+    pub data_len: u64,
+}
@@ -0,0 +1,261 @@
+// src/queue.rs
+//! Write-ahead log of blob submissions.
+//!
+//! Every blob is appended to the log, together with the chain head at that
+//! moment, before `blob_submit` is called, and marked included once a height
+//! comes back. Records are JSON lines and each append is fsynced, so after a
+//! crash [`SubmissionQueue::open`] yields exactly the blobs whose fate is
+//! unknown. The poster then looks for them on chain by commitment and only
+//! resubmits the missing ones (see `CelestiaClient::recover`).
+
+use celestia_types::{Blob, Commitment};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum QueueError {
+    #[error("submission queue {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("submission queue {path} is corrupt at line {line}: {source}")]
+    Corrupt {
+        path: PathBuf,
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+/// A blob that was handed to `blob_submit` without a confirmed height.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingEntry {
+    pub id: u64,
+    /// Chain head when the submission started; inclusion can only be later.
+    pub from_height: u64,
+    pub blob: Blob,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Record {
+    Pending(PendingEntry),
+    Included { id: u64, height: u64 },
+}
+
+#[derive(Debug)]
+pub struct SubmissionQueue {
+    path: PathBuf,
+    file: File,
+    next_id: u64,
+    pending: BTreeMap<u64, PendingEntry>,
+}
+
+impl SubmissionQueue {
+    /// Opens or creates the log at `path` and replays it.
+    ///
+    /// The log is compacted to the still pending entries on open. A torn last
+    /// line, left by a crash in the middle of an append, is dropped.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, QueueError> {
+        let path = path.as_ref().to_path_buf();
+        let io = |source| QueueError::Io { path: path.clone(), source };
+
+        let mut pending = BTreeMap::new();
+        let mut next_id = 0;
+        if path.exists() {
+            let lines: Vec<String> = BufReader::new(File::open(&path).map_err(io)?)
+                .lines()
+                .collect::<Result<_, _>>()
+                .map_err(io)?;
+
+            for (i, line) in lines.iter().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = match serde_json::from_str::<Record>(line) {
+                    Ok(record) => record,
+                    Err(e) if i + 1 == lines.len() => {
+                        tracing::warn!("Dropping torn last record of {}: {}", path.display(), e);
+                        break;
+                    }
+                    Err(source) => {
+                        return Err(QueueError::Corrupt { path, line: i + 1, source });
+                    }
+                };
+                match record {
+                    Record::Pending(entry) => {
+                        next_id = next_id.max(entry.id + 1);
+                        pending.insert(entry.id, entry);
+                    }
+                    Record::Included { id, .. } => {
+                        pending.remove(&id);
+                    }
+                }
+            }
+        }
+
+        let file = compact(&path, pending.values()).map_err(io)?;
+        if !pending.is_empty() {
+            tracing::info!("Submission queue {} has {} pending blobs", path.display(), pending.len());
+        }
+        Ok(Self { path, file, next_id, pending })
+    }
+
+    /// Records that `blob` is about to be submitted and returns its entry id.
+    pub fn push(&mut self, blob: &Blob, from_height: u64) -> Result<u64, QueueError> {
+        let id = self.next_id;
+        self.retry(id, blob, from_height)?;
+        self.next_id += 1;
+        Ok(id)
+    }
+
+    /// Records a new submission attempt of entry `id`, e.g. during recovery.
+    pub fn retry(&mut self, id: u64, blob: &Blob, from_height: u64) -> Result<(), QueueError> {
+        let entry = PendingEntry { id, from_height, blob: blob.clone() };
+        self.append(&Record::Pending(entry.clone()))?;
+        self.pending.insert(id, entry);
+        Ok(())
+    }
+
+    /// Records that entry `id` landed at `height`.
+    pub fn mark_included(&mut self, id: u64, height: u64) -> Result<(), QueueError> {
+        self.append(&Record::Included { id, height })?;
+        self.pending.remove(&id);
+        Ok(())
+    }
+
+    /// Records that every pending entry for `commitment` landed at `height`.
+    pub fn mark_commitment_included(&mut self, commitment: &Commitment, height: u64) -> Result<(), QueueError> {
+        let ids: Vec<u64> = self
+            .pending
+            .values()
+            .filter(|entry| entry.blob.commitment == *commitment)
+            .map(|entry| entry.id)
+            .collect();
+        for id in ids {
+            self.mark_included(id, height)?;
+        }
+        Ok(())
+    }
+
+    /// Oldest pending entry for `commitment`.
+    pub fn find(&self, commitment: &Commitment) -> Option<&PendingEntry> {
+        self.pending.values().find(|entry| entry.blob.commitment == *commitment)
+    }
+
+    /// Entries whose submission outcome is unknown, oldest first.
+    pub fn pending(&self) -> impl Iterator<Item = &PendingEntry> {
+        self.pending.values()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn append(&mut self, record: &Record) -> Result<(), QueueError> {
+        let mut line = serde_json::to_vec(record).expect("queue records serialize to JSON");
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .and_then(|()| self.file.sync_data())
+            .map_err(|source| QueueError::Io { path: self.path.clone(), source })
+    }
+}
+
+/// Rewrites the log with only `pending` and returns it opened for appending.
+fn compact<'a>(path: &Path, pending: impl Iterator<Item = &'a PendingEntry>) -> std::io::Result<File> {
+    let tmp = path.with_extension("compact");
+    {
+        let mut file = File::create(&tmp)?;
+        for entry in pending {
+            let mut line = serde_json::to_vec(&Record::Pending(entry.clone()))?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+        }
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    OpenOptions::new().append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::{consts::appconsts::AppVersion, nmt::Namespace};
+
+    fn blob(data: &[u8]) -> Blob {
+        Blob::new(Namespace::new_v0(b"rollup").unwrap(), data.to_vec(), AppVersion::V2).unwrap()
+    }
+
+    #[test]
+    fn test_replay_keeps_only_unconfirmed_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.jsonl");
+
+        let mut queue = SubmissionQueue::open(&path).unwrap();
+        let a = queue.push(&blob(b"a"), 10).unwrap();
+        let b = queue.push(&blob(b"b"), 10).unwrap();
+        let c = queue.push(&blob(b"c"), 11).unwrap();
+        queue.mark_included(b, 12).unwrap();
+        queue.retry(c, &blob(b"c"), 15).unwrap();
+        drop(queue);
+
+        let mut queue = SubmissionQueue::open(&path).unwrap();
+        let pending: Vec<_> = queue.pending().map(|e| (e.id, e.from_height)).collect();
+        assert_eq!(pending, vec![(a, 10), (c, 15)]);
+        assert_eq!(queue.pending().next().unwrap().blob, blob(b"a"));
+
+        // ids are never reused, even after compaction
+        let d = queue.push(&blob(b"d"), 20).unwrap();
+        assert!(d > c);
+        queue.mark_included(a, 16).unwrap();
+        queue.mark_included(c, 16).unwrap();
+        queue.mark_included(d, 21).unwrap();
+        drop(queue);
+
+        let queue = SubmissionQueue::open(&path).unwrap();
+        assert_eq!(queue.pending().count(), 0);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+    }
+
+    #[test]
+    fn test_entries_by_commitment() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut queue = SubmissionQueue::open(dir.path().join("queue.jsonl")).unwrap();
+        let a = queue.push(&blob(b"a"), 10).unwrap();
+        let b = queue.push(&blob(b"b"), 10).unwrap();
+        queue.push(&blob(b"a"), 12).unwrap();
+        assert_eq!(queue.find(&blob(b"a").commitment).map(|e| e.id), Some(a));
+        assert_eq!(queue.find(&blob(b"c").commitment), None);
+
+        queue.mark_commitment_included(&blob(b"a").commitment, 13).unwrap();
+        assert_eq!(queue.pending().map(|e| e.id).collect::<Vec<_>>(), vec![b]);
+    }
+
+    #[test]
+    fn test_torn_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("queue.jsonl");
+
+        let mut queue = SubmissionQueue::open(&path).unwrap();
+        queue.push(&blob(b"a"), 1).unwrap();
+        drop(queue);
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"op":"included","id":0,"hei"#).unwrap();
+        drop(file);
+        assert_eq!(SubmissionQueue::open(&path).unwrap().pending().count(), 1);
+
+        // garbage before the last line is not a torn write
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, format!("garbage\n{contents}")).unwrap();
+        assert!(matches!(
+            SubmissionQueue::open(&path),
+            Err(QueueError::Corrupt { line: 1, .. })
+        ));
+    }
+}
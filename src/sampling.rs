@@ -0,0 +1,169 @@
+// src/sampling.rs
+//! Availability sampling of posted blobs.
+//!
+//! Inclusion and confirmation depth say nothing about whether the network
+//! actually serves a blob's data. With sampling configured, the poster fetches
+//! random shares from the rows the blob occupies and verifies each against
+//! the row root in the block's data availability header before it hands the
+//! span back. Samples are drawn without repetition from the original data
+//! square; when those rows hold fewer shares than the configured sample
+//! count, every one of them is checked.
+
+use celestia_types::{
+    sample::{Sample, SampleId},
+    AxisType, DataAvailabilityHeader,
+};
+use rand::seq::SliceRandom;
+use std::fmt;
+
+use crate::config::SamplingConfig;
+use crate::error::{Error, Result};
+use crate::poster::{CelestiaClient, SequenceSpan};
+
+/// A share that could not be fetched or did not verify.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleFailure {
+    pub row: u16,
+    pub column: u16,
+    pub reason: String,
+}
+
+impl fmt::Display for SampleFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "share {}:{}: {}", self.row, self.column, self.reason)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvailabilityReport {
+    pub span: SequenceSpan,
+    /// Samples that verified against their row root.
+    pub passed: usize,
+    pub failures: Vec<SampleFailure>,
+}
+
+/// Every `(row, column)` of the original square in the rows `span` occupies.
+pub fn row_positions(span: &SequenceSpan, ods_width: u16) -> Vec<(u16, u16)> {
+    let width = u64::from(ods_width);
+    let first_row = span.start_index / width;
+    let last_row = (span.end_index().max(span.start_index + 1) - 1) / width;
+    (first_row..=last_row)
+        .flat_map(|row| (0..ods_width).map(move |column| (row as u16, column)))
+        .collect()
+}
+
+/// Checks that `sample` is the share at `row`, `column` of the block at `height`.
+pub fn verify_sample(height: u64, row: u16, column: u16, sample: &Sample, dah: &DataAvailabilityHeader) -> Result<()> {
+    // `Sample::verify` takes the position from the proof, so a share proven
+    // at another column of the same row would pass it
+    if sample.proof_type != AxisType::Row || sample.proof.start_idx() != u32::from(column) {
+        return Err(Error::Proof(format!(
+            "sample for share {row}:{column} proves {:?} position {}",
+            sample.proof_type,
+            sample.proof.start_idx()
+        )));
+    }
+    Ok(sample.verify(SampleId::new(row, column, height)?, dah)?)
+}
+
+impl CelestiaClient {
+    /// Samples random shares of the rows `span` occupies.
+    ///
+    /// Succeeds once `config.samples` shares verified, and fails with
+    /// [`Error::Unavailable`] when more than `config.max_failures` could not
+    /// be fetched or verified. Each failure is logged as it happens.
+    pub async fn check_availability(&self, span: &SequenceSpan, config: &SamplingConfig) -> Result<AvailabilityReport> {
+        let backend = self.backend();
+        let dah = backend.header(span.height).await?;
+        let mut positions = row_positions(span, dah.square_width() / 2);
+        positions.shuffle(&mut rand::thread_rng());
+        let required = config.samples.min(positions.len());
+
+        let mut report = AvailabilityReport { span: span.clone(), passed: 0, failures: Vec::new() };
+        for (row, column) in positions {
+            if report.passed >= required {
+                break;
+            }
+            let verified = match backend.sample(span.height, row, column).await {
+                Ok(sample) => verify_sample(span.height, row, column, &sample, &dah).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match verified {
+                Ok(()) => report.passed += 1,
+                Err(reason) => {
+                    let failure = SampleFailure { row, column, reason };
+                    tracing::warn!("Availability sample of {} failed: {}", span, failure);
+                    report.failures.push(failure);
+                    if report.failures.len() > config.max_failures {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if report.passed < required {
+            return Err(Error::Unavailable {
+                span: span.clone(),
+                passed: report.passed,
+                required,
+                failures: report.failures,
+            });
+        }
+        tracing::info!(
+            "Span {} is available: {} samples verified, {} failed",
+            span,
+            report.passed,
+            report.failures.len()
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{DaBackend, MemoryBackend};
+    use crate::config::CelestiaConfig;
+    use celestia_types::{consts::appconsts::AppVersion, nmt::Namespace, Blob, TxConfig};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_row_positions() {
+        let span = SequenceSpan { height: 1, start_index: 6, data_len: 3, commitment: None };
+        let positions = row_positions(&span, 4);
+        assert_eq!(positions.len(), 8);
+        assert_eq!((positions[0], positions[7]), ((1, 0), (2, 3)));
+    }
+
+    #[tokio::test]
+    async fn test_check_availability() {
+        let namespace = Namespace::new_v0(b"rollup").unwrap();
+        let backend = Arc::new(MemoryBackend::new());
+        let blob = Blob::new(namespace, vec![7; 2000], AppVersion::V2).unwrap();
+        let height = backend.submit(&[blob], TxConfig::default()).await.unwrap();
+        let dah = backend.header(height).await.unwrap();
+
+        let sample = backend.sample(height, 0, 1).await.unwrap();
+        verify_sample(height, 0, 1, &sample, &dah).unwrap();
+        // a share presented for another position, or altered, does not verify
+        assert!(verify_sample(height, 0, 2, &sample, &dah).is_err());
+        let mut altered = sample.clone();
+        let mut data = altered.share.as_ref().to_vec();
+        data[100] ^= 1;
+        altered.share = celestia_types::Share::from_raw(&data).unwrap();
+        assert!(verify_sample(height, 0, 1, &altered, &dah).is_err());
+
+        let config = CelestiaConfig { namespace, ..CelestiaConfig::default() };
+        let client = CelestiaClient::with_backend(&config, Box::new(backend.clone())).unwrap();
+        let span = SequenceSpan { height, start_index: 0, data_len: 5, commitment: None };
+        let sampling = SamplingConfig { samples: 6, max_failures: 1 };
+        let report = client.check_availability(&span, &sampling).await.unwrap();
+        assert_eq!((report.passed, report.failures.len()), (6, 0));
+
+        backend.withhold_shares(height);
+        match client.check_availability(&span, &sampling).await {
+            Err(Error::Unavailable { passed: 0, required: 6, failures, .. }) => assert_eq!(failures.len(), 2),
+            other => panic!("expected the span to be unavailable, got {other:?}"),
+        }
+    }
+}
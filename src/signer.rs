@@ -0,0 +1,307 @@
+// src/signer.rs
+//! Signers for L1 settlement transactions.
+//!
+//! Key material never leaves these types: their `Debug` output only shows the
+//! signing address and errors never echo the key, so signers are safe to pass
+//! to `tracing`.
+
+use alloy::{
+    consensus::SignableTransaction,
+    network::{EthereumWallet, TxSigner},
+    primitives::{keccak256, Address, PrimitiveSignature as Signature},
+    signers::local::{LocalSignerError, PrivateKeySigner},
+};
+use async_trait::async_trait;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::SignerConfig;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SignerError {
+    #[error("environment variable {0} is not set")]
+    MissingEnv(String),
+
+    #[error("environment variable {0} does not hold a valid private key")]
+    InvalidKey(String),
+
+    #[error("failed to decrypt keystore {path}: {source}")]
+    Keystore {
+        path: PathBuf,
+        source: LocalSignerError,
+    },
+
+    #[error("remote signer request failed: {0}")]
+    Remote(#[from] reqwest::Error),
+
+    #[error("remote signer returned a malformed signature: {0}")]
+    MalformedSignature(String),
+
+    #[error("remote signer produced a signature for {actual}, expected {expected}")]
+    AddressMismatch { expected: Address, actual: Address },
+}
+
+/// Source of the account that signs settlement transactions.
+pub trait L1Signer: Send + Sync + fmt::Debug {
+    fn address(&self) -> Address;
+
+    /// Wallet used by the alloy provider to sign outgoing transactions.
+    fn wallet(&self) -> EthereumWallet;
+}
+
+/// Builds the signer backend selected in the config.
+pub fn from_config(config: &SignerConfig) -> Result<Box<dyn L1Signer>, SignerError> {
+    let signer: Box<dyn L1Signer> = match config {
+        SignerConfig::Env { var } => Box::new(LocalKeySigner::from_env(var)?),
+        SignerConfig::Keystore { path, password_env } => {
+            let password = std::env::var(password_env)
+                .map_err(|_| SignerError::MissingEnv(password_env.clone()))?;
+            Box::new(LocalKeySigner::from_keystore(path, password)?)
+        }
+        SignerConfig::Remote { url, address } => Box::new(RemoteSigner::new(url.clone(), *address)),
+    };
+    tracing::info!("Using L1 signer {:?}", signer);
+    Ok(signer)
+}
+
+/// Signer holding a secp256k1 key in memory.
+#[derive(Clone)]
+pub struct LocalKeySigner {
+    inner: PrivateKeySigner,
+}
+
+impl LocalKeySigner {
+    /// Reads a hex encoded (optionally `0x` prefixed) private key from `var`.
+    pub fn from_env(var: &str) -> Result<Self, SignerError> {
+        let key = std::env::var(var).map_err(|_| SignerError::MissingEnv(var.to_string()))?;
+        let inner = key
+            .trim()
+            .trim_start_matches("0x")
+            .parse()
+            .map_err(|_| SignerError::InvalidKey(var.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// Decrypts an encrypted JSON (web3 secret storage) keystore.
+    pub fn from_keystore(path: &Path, password: impl AsRef<[u8]>) -> Result<Self, SignerError> {
+        let inner = PrivateKeySigner::decrypt_keystore(path, password).map_err(|source| {
+            SignerError::Keystore {
+                path: path.to_path_buf(),
+                source,
+            }
+        })?;
+        Ok(Self { inner })
+    }
+}
+
+impl fmt::Debug for LocalKeySigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalKeySigner")
+            .field("address", &self.inner.address())
+            .finish_non_exhaustive()
+    }
+}
+
+impl L1Signer for LocalKeySigner {
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn wallet(&self) -> EthereumWallet {
+        EthereumWallet::from(self.inner.clone())
+    }
+}
+
+#[derive(serde::Serialize)]
+struct RemoteSignRequest {
+    data: String,
+}
+
+/// Signer delegating to a web3signer-compatible service.
+///
+/// Uses `POST {url}/api/v1/eth1/sign/{address}`, which signs the keccak256
+/// hash of `data` and answers with the hex encoded 65 byte signature.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    address: Address,
+}
+
+impl RemoteSigner {
+    pub fn new(url: String, address: Address) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            address,
+        }
+    }
+
+    /// Signs `keccak256(payload)` and checks that the remote key matches `address`.
+    pub async fn sign_payload(&self, payload: &[u8]) -> Result<Signature, SignerError> {
+        let response = self
+            .client
+            .post(format!("{}/api/v1/eth1/sign/{}", self.url, self.address))
+            .json(&RemoteSignRequest {
+                data: format!("0x{}", hex::encode(payload)),
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let body = response.trim().trim_matches('"');
+        let bytes = hex::decode(body.trim_start_matches("0x"))
+            .map_err(|e| SignerError::MalformedSignature(e.to_string()))?;
+        let signature = Signature::try_from(bytes.as_slice())
+            .map_err(|e| SignerError::MalformedSignature(e.to_string()))?;
+
+        let actual = signature
+            .recover_address_from_prehash(&keccak256(payload))
+            .map_err(|e| SignerError::MalformedSignature(e.to_string()))?;
+        if actual != self.address {
+            return Err(SignerError::AddressMismatch {
+                expected: self.address,
+                actual,
+            });
+        }
+
+        Ok(signature)
+    }
+}
+
+#[async_trait]
+impl TxSigner<Signature> for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> alloy::signers::Result<Signature> {
+        let payload = tx.encoded_for_signing();
+        self.sign_payload(&payload)
+            .await
+            .map_err(alloy::signers::Error::other)
+    }
+}
+
+impl L1Signer for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn wallet(&self) -> EthereumWallet {
+        EthereumWallet::from(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::{
+        consensus::TxEip1559,
+        primitives::U256,
+        signers::SignerSync,
+    };
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server,
+    };
+    use std::convert::Infallible;
+
+    const KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    /// Minimal web3signer stand-in that signs with `key`.
+    async fn spawn_stub_signer(key: PrivateKeySigner) -> String {
+        let make_service = make_service_fn(move |_| {
+            let key = key.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let key = key.clone();
+                    async move {
+                        assert!(req.uri().path().starts_with("/api/v1/eth1/sign/"));
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                        let data = hex::decode(
+                            request["data"].as_str().unwrap().trim_start_matches("0x"),
+                        )
+                        .unwrap();
+                        let signature = key.sign_hash_sync(&keccak256(&data)).unwrap();
+                        let body = format!("0x{}", hex::encode(signature.as_bytes()));
+                        Ok::<_, Infallible>(Response::new(Body::from(body)))
+                    }
+                }))
+            }
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        url
+    }
+
+    #[test]
+    fn test_env_signer_is_redacted() {
+        std::env::set_var("VOLITION_TEST_SIGNER_KEY", format!("0x{KEY}"));
+        let signer = LocalKeySigner::from_env("VOLITION_TEST_SIGNER_KEY").unwrap();
+
+        let debug = format!("{:?}", signer);
+        assert!(debug.contains(&format!("{:?}", signer.address())));
+        assert!(!debug.contains(KEY));
+
+        std::env::set_var("VOLITION_TEST_SIGNER_BAD_KEY", "not a key");
+        let err = LocalKeySigner::from_env("VOLITION_TEST_SIGNER_BAD_KEY").unwrap_err();
+        assert!(matches!(err, SignerError::InvalidKey(_)));
+        assert!(matches!(
+            LocalKeySigner::from_env("VOLITION_TEST_SIGNER_UNSET").unwrap_err(),
+            SignerError::MissingEnv(_)
+        ));
+    }
+
+    #[test]
+    fn test_keystore_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = hex::decode(KEY).unwrap();
+        let (expected, name) = PrivateKeySigner::encrypt_keystore(
+            dir.path(),
+            &mut rand::thread_rng(),
+            key,
+            "hunter2",
+            None,
+        )
+        .unwrap();
+
+        let signer = LocalKeySigner::from_keystore(&dir.path().join(name), "hunter2").unwrap();
+        assert_eq!(signer.address(), expected.address());
+
+        let err = LocalKeySigner::from_keystore(&dir.path().join("missing"), "hunter2").unwrap_err();
+        assert!(matches!(err, SignerError::Keystore { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer() {
+        let key: PrivateKeySigner = KEY.parse().unwrap();
+        let url = spawn_stub_signer(key.clone()).await;
+
+        let signer = RemoteSigner::new(url.clone(), key.address());
+        let mut tx = TxEip1559 {
+            chain_id: 11155111,
+            nonce: 1,
+            gas_limit: 21000,
+            value: U256::from(1),
+            ..Default::default()
+        };
+        let signature = signer.sign_transaction(&mut tx).await.unwrap();
+        assert_eq!(
+            signature.recover_address_from_prehash(&tx.signature_hash()).unwrap(),
+            key.address()
+        );
+
+        let wrong = RemoteSigner::new(url, Address::repeat_byte(0x11));
+        let err = wrong.sign_payload(b"payload").await.unwrap_err();
+        assert!(matches!(err, SignerError::AddressMismatch { .. }));
+    }
+}
@@ -0,0 +1,405 @@
+// src/span.rs
+//! Locating blobs in the original data square (ODS).
+//!
+//! Spans handed to the settlement contract index shares row-major in the ODS,
+//! while celestia-node reports blob positions in the extended square and
+//! namespace proofs only carry row-local offsets. Everything that turns chain
+//! data into a [`SequenceSpan`] goes through this module.
+
+use celestia_types::{
+    consts::appconsts::{CONTINUATION_SPARSE_SHARE_CONTENT_SIZE, FIRST_SPARSE_SHARE_CONTENT_SIZE},
+    consts::appconsts::AppVersion,
+    nmt::{Namespace, NamespacedSha2Hasher},
+    row_namespace_data::{NamespaceData, RowNamespaceDataId},
+    Blob, Commitment, DataAvailabilityHeader, Share,
+};
+
+use crate::error::{Error, Result};
+use crate::poster::SequenceSpan;
+
+/// Converts an index in the extended square of width `eds_width` to the ODS index.
+///
+/// Only shares of the first quadrant have an ODS index; callers pass blob
+/// positions, which always lie there.
+pub fn ods_index(eds_index: u64, eds_width: u16) -> u64 {
+    let eds_width = u64::from(eds_width);
+    let row = eds_index / eds_width;
+    let col = eds_index % eds_width;
+    row * (eds_width / 2) + col
+}
+
+/// Number of shares a blob of `data_len` bytes occupies.
+pub fn blob_share_count(data_len: usize) -> u64 {
+    if data_len <= FIRST_SPARSE_SHARE_CONTENT_SIZE {
+        return 1;
+    }
+    let rest = data_len - FIRST_SPARSE_SHARE_CONTENT_SIZE;
+    1 + rest.div_ceil(CONTINUATION_SPARSE_SHARE_CONTENT_SIZE) as u64
+}
+
+/// Span of a blob retrieved from the chain (`blob_get` / `blob_get_all`).
+///
+/// `eds_width` is the extended square width of the block, i.e.
+/// `header.dah.square_width()`.
+pub fn blob_span(height: u64, blob: &Blob, eds_width: u16) -> Result<SequenceSpan> {
+    let eds_index = blob.index.ok_or(Error::BlobNotFound {
+        height,
+        commitment: blob.commitment,
+    })?;
+
+    Ok(SequenceSpan {
+        height,
+        start_index: ods_index(eds_index, eds_width),
+        data_len: blob_share_count(blob.data.len()),
+        commitment: Some(blob.commitment),
+    })
+}
+
+/// First row whose root covers `namespace`, if any.
+pub fn namespace_start_row(dah: &DataAvailabilityHeader, namespace: Namespace) -> Option<u16> {
+    dah.row_roots()
+        .iter()
+        .position(|root| root.contains::<NamespacedSha2Hasher>(*namespace))
+        .map(|row| row as u16)
+}
+
+/// Pairs every share of `namespace_data` with its ODS index.
+///
+/// `share_get_namespace_data` returns one entry per consecutive row holding
+/// the namespace, each starting at the row-local offset of its proof.
+fn indexed_shares<'a>(
+    namespace_data: &'a NamespaceData,
+    dah: &DataAvailabilityHeader,
+) -> Vec<(u64, &'a Share)> {
+    let Some(namespace) = namespace_data
+        .rows
+        .iter()
+        .find_map(|row| row.shares.first())
+        .map(Share::namespace)
+    else {
+        return Vec::new();
+    };
+    let Some(start_row) = namespace_start_row(dah, namespace) else {
+        return Vec::new();
+    };
+    let ods_width = u64::from(dah.square_width() / 2);
+
+    namespace_data
+        .rows
+        .iter()
+        .enumerate()
+        .flat_map(|(offset, row)| {
+            let first = (u64::from(start_row) + offset as u64) * ods_width
+                + u64::from(row.proof.start_idx());
+            row.shares
+                .iter()
+                .enumerate()
+                .map(move |(i, share)| (first + i as u64, share))
+        })
+        .collect()
+}
+
+/// Checks every row of `namespace_data` against the row roots of `dah`.
+///
+/// Shares must all be of `namespace`, and rows are only accepted where the
+/// row roots cover the namespace.
+pub fn verify_namespace_data(
+    height: u64,
+    namespace: Namespace,
+    namespace_data: &NamespaceData,
+    dah: &DataAvailabilityHeader,
+) -> Result<()> {
+    let shares = namespace_data.rows.iter().flat_map(|row| &row.shares);
+    if let Some(share) = shares.into_iter().find(|share| share.namespace() != namespace) {
+        return Err(Error::Proof(format!(
+            "share of namespace {:?} served for {:?} at height {height}",
+            share.namespace(),
+            namespace
+        )));
+    }
+    let Some(start_row) = namespace_start_row(dah, namespace) else {
+        if namespace_data.rows.is_empty() {
+            return Ok(());
+        }
+        return Err(Error::Proof(format!(
+            "no row at height {height} covers namespace {namespace:?}, yet {} rows were served",
+            namespace_data.rows.len()
+        )));
+    };
+    for (offset, row) in namespace_data.rows.iter().enumerate() {
+        let id = RowNamespaceDataId::new(namespace, start_row + offset as u16, height)?;
+        row.verify(id, dah)?;
+    }
+    Ok(())
+}
+
+/// Span covering every share of the namespace at `height`.
+pub fn namespace_span(
+    height: u64,
+    namespace_data: &NamespaceData,
+    dah: &DataAvailabilityHeader,
+) -> Option<SequenceSpan> {
+    let shares = indexed_shares(namespace_data, dah);
+    let (start_index, _) = shares.first()?;
+
+    Some(SequenceSpan {
+        height,
+        start_index: *start_index,
+        data_len: shares.len() as u64,
+        commitment: None,
+    })
+}
+
+/// Finds the blob with `commitment` among the namespace shares and returns its span.
+pub fn find_blob_span(
+    height: u64,
+    namespace_data: &NamespaceData,
+    dah: &DataAvailabilityHeader,
+    commitment: &Commitment,
+) -> Result<SequenceSpan> {
+    let shares = indexed_shares(namespace_data, dah);
+    let not_found = || Error::BlobNotFound {
+        height,
+        commitment: *commitment,
+    };
+
+    let mut i = 0;
+    while i < shares.len() {
+        let (start_index, share) = shares[i];
+        let Some(sequence_len) = share.sequence_length() else {
+            // continuation share of a blob we could not parse; skip it
+            i += 1;
+            continue;
+        };
+
+        let count = blob_share_count(sequence_len as usize) as usize;
+        let Some(blob_shares) = shares.get(i..i + count) else {
+            return Err(not_found());
+        };
+        let blob = Blob::reconstruct(blob_shares.iter().map(|(_, share)| *share), AppVersion::V2)?;
+        if &blob.commitment == commitment {
+            return Ok(SequenceSpan {
+                height,
+                start_index,
+                data_len: count as u64,
+                commitment: Some(blob.commitment),
+            });
+        }
+        i += count;
+    }
+
+    Err(not_found())
+}
+
+/// Rebuilds the blob `span` points at from the namespace shares of its block.
+///
+/// The shares must hold exactly one blob in the sparse share format: a
+/// sequence start share whose declared length needs `span.data_len` shares,
+/// followed by its continuation shares. The blob is re-committed locally and
+/// must match `commitment`.
+pub fn span_blob(
+    span: &SequenceSpan,
+    namespace_data: &NamespaceData,
+    dah: &DataAvailabilityHeader,
+    commitment: &Commitment,
+) -> Result<Blob> {
+    let mismatch = |reason: String| Error::SpanMismatch { span: span.clone(), reason };
+
+    let shares: Vec<&Share> = indexed_shares(namespace_data, dah)
+        .into_iter()
+        .filter(|(index, _)| (span.start_index..span.end_index()).contains(index))
+        .map(|(_, share)| share)
+        .collect();
+    if shares.len() as u64 != span.data_len {
+        return Err(mismatch(format!("namespace holds {} of its shares", shares.len())));
+    }
+
+    let sequence_len = shares
+        .first()
+        .and_then(|share| share.sequence_length())
+        .ok_or_else(|| mismatch("first share does not start a blob".to_string()))?;
+    let needed = blob_share_count(sequence_len as usize);
+    if needed != span.data_len {
+        return Err(mismatch(format!("blob of {sequence_len} bytes needs {needed} shares")));
+    }
+
+    let blob = Blob::reconstruct(shares, AppVersion::V2)?;
+    if &blob.commitment != commitment {
+        return Err(mismatch("reconstructed blob does not match the commitment".to_string()));
+    }
+    Ok(blob)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use celestia_types::{consts::appconsts::SHARE_SIZE, nmt::NS_SIZE, ExtendedDataSquare};
+
+    const ODS_WIDTH: usize = 4;
+
+    /// Builds an 8x8 EDS whose ODS holds `blobs` in order followed by tail padding.
+    fn square(blobs: &[&Blob]) -> (ExtendedDataSquare, DataAvailabilityHeader) {
+        let mut ods: Vec<Vec<u8>> = blobs
+            .iter()
+            .flat_map(|blob| blob.to_shares().unwrap())
+            .map(|share| share.to_vec())
+            .collect();
+        ods.resize(
+            ODS_WIDTH * ODS_WIDTH,
+            [Namespace::TAIL_PADDING.as_bytes(), &[0; SHARE_SIZE - NS_SIZE][..]].concat(),
+        );
+
+        let eds = ExtendedDataSquare::from_ods(ods, AppVersion::V2).unwrap();
+        let dah = DataAvailabilityHeader::from_eds(&eds);
+        (eds, dah)
+    }
+
+    fn namespace_data(
+        eds: &ExtendedDataSquare,
+        dah: &DataAvailabilityHeader,
+        namespace: Namespace,
+    ) -> NamespaceData {
+        NamespaceData {
+            rows: eds
+                .get_namespace_data(namespace, dah, 1)
+                .unwrap()
+                .into_iter()
+                .map(|(_, row)| row)
+                .collect(),
+        }
+    }
+
+    fn blob(namespace: Namespace, len: usize) -> Blob {
+        Blob::new(namespace, vec![len as u8; len], AppVersion::V2).unwrap()
+    }
+
+    #[test]
+    fn test_ods_index() {
+        // row 1, column 2 of an 8 wide EDS is share 6 of the 4 wide ODS
+        assert_eq!(ods_index(10, 8), 6);
+        assert_eq!(ods_index(0, 8), 0);
+        assert_eq!(ods_index(27, 8), 15);
+    }
+
+    #[test]
+    fn test_blob_share_count() {
+        assert_eq!(blob_share_count(0), 1);
+        assert_eq!(blob_share_count(FIRST_SPARSE_SHARE_CONTENT_SIZE), 1);
+        assert_eq!(blob_share_count(FIRST_SPARSE_SHARE_CONTENT_SIZE + 1), 2);
+        assert_eq!(
+            blob_share_count(FIRST_SPARSE_SHARE_CONTENT_SIZE + 2 * CONTINUATION_SPARSE_SHARE_CONTENT_SIZE),
+            3
+        );
+
+        let blob = blob(Namespace::new_v0(b"filler").unwrap(), 2000);
+        assert_eq!(blob_share_count(blob.data.len()), blob.to_shares().unwrap().len() as u64);
+    }
+
+    #[test]
+    fn test_blob_spanning_rows_among_other_blobs() {
+        let other = Namespace::new_v0(b"filler").unwrap();
+        let ours = Namespace::new_v0(b"rollup").unwrap();
+        // 3 shares of another namespace push our blobs off the row start:
+        // the small blob takes share 3, the large one shares 4..7 on the next row
+        let filler = blob(other, 1000);
+        let small = blob(ours, 10);
+        let large = blob(ours, 1000);
+        let (eds, dah) = square(&[&filler, &small, &large]);
+        let data = namespace_data(&eds, &dah, ours);
+        assert_eq!(data.rows.len(), 2);
+
+        let span = find_blob_span(1, &data, &dah, &small.commitment).unwrap();
+        assert_eq!((span.start_index, span.data_len), (3, 1));
+
+        let span = find_blob_span(1, &data, &dah, &large.commitment).unwrap();
+        assert_eq!((span.start_index, span.data_len), (4, 3));
+        assert_eq!(span.commitment, Some(large.commitment));
+
+        let span = namespace_span(1, &data, &dah).unwrap();
+        assert_eq!((span.start_index, span.data_len), (3, 4));
+
+        let missing = blob(ours, 11);
+        let err = find_blob_span(1, &data, &dah, &missing.commitment).unwrap_err();
+        assert!(matches!(err, Error::BlobNotFound { height: 1, .. }));
+    }
+
+    #[test]
+    fn test_blob_span_from_eds_index() {
+        let ns = Namespace::new_v0(b"rollup").unwrap();
+        let filler = blob(Namespace::new_v0(b"filler").unwrap(), 2000);
+        let ours = blob(ns, 600);
+        let (eds, dah) = square(&[&filler, &ours]);
+        let data = namespace_data(&eds, &dah, ns);
+
+        // filler occupies 5 shares, so ours starts at ODS 5 = row 1, column 1 = EDS 9
+        let retrieved = Blob { index: Some(9), ..ours.clone() };
+        let span = blob_span(1, &retrieved, dah.square_width()).unwrap();
+        assert_eq!(span, find_blob_span(1, &data, &dah, &ours.commitment).unwrap());
+        assert_eq!((span.start_index, span.data_len), (5, 2));
+
+        assert!(blob_span(1, &ours, dah.square_width()).is_err());
+    }
+
+    #[test]
+    fn test_span_blob() {
+        let ns = Namespace::new_v0(b"rollup").unwrap();
+        let filler = blob(Namespace::new_v0(b"filler").unwrap(), 1000);
+        let small = blob(ns, 10);
+        let large = Blob::new(ns, (0..1000u32).map(|i| i as u8).collect(), AppVersion::V2).unwrap();
+        let (eds, dah) = square(&[&filler, &small, &large]);
+        let data = namespace_data(&eds, &dah, ns);
+        verify_namespace_data(1, ns, &data, &dah).unwrap();
+
+        let span = find_blob_span(1, &data, &dah, &large.commitment).unwrap();
+        assert_eq!(span_blob(&span, &data, &dah, &large.commitment).unwrap().data, large.data);
+
+        let err = span_blob(&span, &data, &dah, &small.commitment).unwrap_err();
+        assert!(matches!(err, Error::SpanMismatch { .. }));
+        // starting on a continuation share
+        let shifted = SequenceSpan { start_index: 5, data_len: 2, ..span.clone() };
+        assert!(span_blob(&shifted, &data, &dah, &large.commitment).is_err());
+        // covering two blobs
+        let wide = SequenceSpan { start_index: 3, data_len: 4, ..span.clone() };
+        assert!(span_blob(&wide, &data, &dah, &small.commitment).is_err());
+        let past_end = SequenceSpan { data_len: 5, ..span };
+        assert!(span_blob(&past_end, &data, &dah, &large.commitment).is_err());
+
+        // shares that do not belong to the square are rejected
+        let mut tampered = data.clone();
+        tampered.rows[1].shares.swap(0, 1);
+        assert!(verify_namespace_data(1, ns, &tampered, &dah).is_err());
+        // as are shares of another namespace
+        let filler_ns = Namespace::new_v0(b"filler").unwrap();
+        assert!(verify_namespace_data(1, filler_ns, &data, &dah).is_err());
+    }
+
+    #[test]
+    fn test_absent_namespace() {
+        let ns = Namespace::new_v0(b"rollup").unwrap();
+        let (eds, dah) = square(&[&blob(ns, 10)]);
+        let data = namespace_data(&eds, &dah, ns);
+
+        // sorts before every row root, so no row can hold it
+        let absent = Namespace::new_v0(b"aaa").unwrap();
+        assert_eq!(namespace_start_row(&dah, absent), None);
+        verify_namespace_data(1, absent, &NamespaceData { rows: Vec::new() }, &dah).unwrap();
+
+        // made-up shares claiming the absent namespace
+        let mut forged = data.clone();
+        for row in &mut forged.rows {
+            for share in &mut row.shares {
+                let mut raw = share.as_ref().to_vec();
+                raw[..NS_SIZE].copy_from_slice(absent.as_bytes());
+                *share = Share::from_raw(&raw).unwrap();
+            }
+        }
+        assert!(verify_namespace_data(1, absent, &forged, &dah).is_err());
+    }
+
+    #[test]
+    fn test_empty_namespace() {
+        let (eds, dah) = square(&[&blob(Namespace::new_v0(b"filler").unwrap(), 10)]);
+        let data = namespace_data(&eds, &dah, Namespace::new_v0(b"rollup").unwrap());
+        assert!(namespace_span(1, &data, &dah).is_none());
+    }
+}